@@ -1,42 +1,139 @@
-use std::{future::Future, task::Poll, thread, time::Duration};
+use std::{
+    cell::RefCell,
+    future::Future,
+    sync::{Arc, Condvar, Mutex},
+    task::{Poll, Waker},
+    thread,
+    time::Duration,
+};
 
 pub struct AsyncTimer {
     duration: Duration,
-    started: bool,
+    // Shared with the timer thread once the timer is started
+    shared: Option<Arc<Shared>>,
+    // Set instead of a timer thread if the timer runs on a `Clock`
+    scheduled: Option<(Arc<dyn Clock>, u64)>,
+}
+
+/// Runs timers on another clock than the system's, e.g. the virtual clock
+/// of a simulation that skips ahead whenever nothing else happens
+pub trait Clock: Send + Sync {
+    /// Call `expire` once `duration` passed on this clock, returns an id
+    /// for `cancel`
+    fn schedule(&self, duration: Duration, expire: Box<dyn FnOnce() + Send>) -> u64;
+
+    /// Forget a timer that didn't expire yet
+    fn cancel(&self, id: u64);
+}
+
+thread_local! {
+    static CLOCK: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+}
+
+/// Run the timers started on this thread from now on on `clock`, or on
+/// threads again with `None`. Timers that were started already keep their
+/// clock.
+pub fn set_clock(clock: Option<Arc<dyn Clock>>) {
+    CLOCK.set(clock);
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Wakes the timer thread early when the timer is dropped
+    cancelled: Condvar,
+}
+
+struct State {
+    expired: bool,
+    cancelled: bool,
+    waker: Waker,
 }
 
 impl AsyncTimer {
     pub fn new(duration: Duration) -> Self {
         AsyncTimer {
             duration,
-            started: false,
+            shared: None,
+            scheduled: None,
         }
     }
 }
 
+impl Shared {
+    fn expire(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.cancelled {
+            return;
+        }
+        state.expired = true;
+        let waker = state.waker.clone();
+        drop(state);
+        println!(
+            "Timer expired! Calling waker.wake() \
+            to tell the runtime that the future is ready to be polled again..."
+        );
+        waker.wake();
+    }
+}
+
 impl Future for AsyncTimer {
     type Output = ();
     fn poll(
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        if !self.started {
-            self.started = true;
+        let Some(shared) = &self.shared else {
+            let shared = Arc::new(Shared {
+                state: Mutex::new(State {
+                    expired: false,
+                    cancelled: false,
+                    waker: cx.waker().clone(),
+                }),
+                cancelled: Condvar::new(),
+            });
+            self.shared = Some(shared.clone());
 
-            let duration = self.duration.clone();
-            let waker = cx.waker().clone();
+            let duration = self.duration;
+            if let Some(clock) = CLOCK.with_borrow(Clone::clone) {
+                let id = clock.schedule(duration, Box::new(move || shared.expire()));
+                self.scheduled = Some((clock, id));
+                return Poll::Pending;
+            }
             // In a real async runtime, you wouldn't spawn a thread like this,
             // but use syscalls instead to make use of timers and events provided by the OS.
             thread::spawn(move || {
-                thread::sleep(duration);
-                println!(
-                    "Timer expired! Calling waker.wake() \
-                    to tell the runtime that the future is ready to be polled again..."
-                );
-                waker.wake();
+                let state = shared.state.lock().unwrap();
+                let (state, _) = shared
+                    .cancelled
+                    .wait_timeout_while(state, duration, |state| !state.cancelled)
+                    .unwrap();
+                drop(state);
+                shared.expire();
             });
             return Poll::Pending;
+        };
+        // The future can be polled before the timer expired, e.g. when it
+        // runs next to other futures in the same task
+        let mut state = shared.state.lock().unwrap();
+        if state.expired {
+            return Poll::Ready(());
+        }
+        if !state.waker.will_wake(cx.waker()) {
+            state.waker = cx.waker().clone();
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for AsyncTimer {
+    /// Stop the timer thread if the timer is dropped before it expired
+    fn drop(&mut self) {
+        if let Some(shared) = &self.shared {
+            shared.state.lock().unwrap().cancelled = true;
+            shared.cancelled.notify_one();
+        }
+        if let Some((clock, id)) = &self.scheduled {
+            clock.cancel(*id);
         }
-        Poll::Ready(())
     }
 }
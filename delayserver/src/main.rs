@@ -0,0 +1,245 @@
+//! Serves `GET /<delay in ms>/<message>` by waiting for the delay and echoing the message.
+//! This is the server the `timer_event_queue` demo talks to:
+//!
+//! ```text
+//! cargo run --bin delayserver -- --port 8080 --concurrency 100
+//! ```
+use async_timer::AsyncTimer;
+use runtime::{
+    Executor,
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+use std::{io, sync::Arc, time::Duration};
+
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+struct Config {
+    port: u16,
+    concurrency: usize,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    let mut config = Config {
+        port: 8080,
+        concurrency: 1000,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("missing value for {name}"));
+        match arg.as_str() {
+            "-p" | "--port" => {
+                config.port = value(&arg)?.parse().map_err(|e| format!("{arg}: {e}"))?;
+            }
+            "-c" | "--concurrency" => {
+                config.concurrency = value(&arg)?.parse().map_err(|e| format!("{arg}: {e}"))?;
+                if config.concurrency == 0 {
+                    return Err(format!("{arg} must be at least 1"));
+                }
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    Ok(config)
+}
+
+async fn read_request_line(stream: &TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        if head.len() > MAX_HEAD_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request too large",
+            ));
+        }
+        match stream.read(&mut chunk).await? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => head.extend_from_slice(&chunk[..n]),
+        }
+    }
+    let head = String::from_utf8_lossy(&head);
+    Ok(head.lines().next().unwrap_or_default().to_string())
+}
+
+/// Split `/<delay>/<message>` into its parts
+fn parse_path(request_line: &str) -> Option<(u64, String)> {
+    let mut parts = request_line.split(' ');
+    let (method, path) = (parts.next()?, parts.next()?);
+    if method != "GET" {
+        return None;
+    }
+    let (delay, message) = path.strip_prefix('/')?.split_once('/')?;
+    Some((delay.parse().ok()?, message.to_string()))
+}
+
+async fn handle(stream: TcpStream, id: usize) -> io::Result<()> {
+    let request_line = read_request_line(&stream).await?;
+    let (status, body) = match parse_path(&request_line) {
+        Some((delay, message)) => {
+            println!("#{id} - {delay}ms: {message}");
+            AsyncTimer::new(Duration::from_millis(delay)).await;
+            ("200 OK", message)
+        }
+        None => (
+            "400 Bad Request",
+            "expected GET /<delay in ms>/<message>".to_string(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+            Content-Type: text/plain\r\n\
+            Content-Length: {}\r\n\
+            Connection: close\r\n\
+            \r\n\
+            {body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown(std::net::Shutdown::Write)
+}
+
+async fn serve(listener: TcpListener, concurrency: usize) {
+    // The accept loop waits for a free permit before it accepts the next connection
+    let limit = Arc::new(Semaphore::new(concurrency));
+    let mut next_id = 0;
+    loop {
+        let permit = limit
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("the semaphore is never closed");
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("ERROR: accept failed: {e}");
+                continue;
+            }
+        };
+        let id = next_id;
+        next_id += 1;
+        runtime::spawn(async move {
+            if let Err(e) = handle(stream, id).await {
+                eprintln!("ERROR: #{id}: {e}");
+            }
+            drop(permit);
+        });
+    }
+}
+
+fn main() {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}\nusage: delayserver [--port <port>] [--concurrency <max connections>]");
+            std::process::exit(2);
+        }
+    };
+    let listener = match TcpListener::bind(("127.0.0.1", config.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("ERROR: could not bind to port {}: {e}", config.port);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Delay server listening on localhost:{} (max {} connections)",
+        config.port, config.concurrency
+    );
+    let mut executor = Executor::new();
+    executor.schedule(serve(listener, config.concurrency));
+    executor.block();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtime::{future::race, http::Client, join};
+    use std::{cell::RefCell, future::Future, rc::Rc, time::Instant};
+
+    fn args(args: &str) -> Result<Config, String> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    /// Run `test` against a server on a free port with the given concurrency
+    fn with_server<F: Future<Output = ()> + 'static>(
+        concurrency: usize,
+        test: impl FnOnce(String) -> F + 'static,
+    ) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let done = Rc::new(RefCell::new(false));
+        let finished = done.clone();
+        let mut executor = Executor::new();
+        executor.schedule(async move {
+            let server = async {
+                serve(listener, concurrency).await;
+                unreachable!("the server runs forever");
+            };
+            race(server, test(url)).await;
+            *finished.borrow_mut() = true;
+        });
+        executor.block();
+        assert!(*done.borrow());
+    }
+
+    #[test]
+    fn parses_the_arguments() {
+        let config = args("").unwrap();
+        assert_eq!((config.port, config.concurrency), (8080, 1000));
+        let config = args("--port 9000 -c 4").unwrap();
+        assert_eq!((config.port, config.concurrency), (9000, 4));
+        assert!(args("-c 0").unwrap_err().contains("at least 1"));
+        assert!(args("--port").unwrap_err().contains("missing value"));
+        assert!(args("--port x").is_err());
+        assert!(args("--verbose").unwrap_err().contains("unknown argument"));
+    }
+
+    #[test]
+    fn parses_delay_and_message_from_the_path() {
+        assert_eq!(
+            parse_path("GET /1500/request-3 HTTP/1.1"),
+            Some((1500, "request-3".to_string()))
+        );
+        assert_eq!(parse_path("GET /10/ HTTP/1.1"), Some((10, String::new())));
+        assert_eq!(parse_path("POST /10/x HTTP/1.1"), None);
+        assert_eq!(parse_path("GET /soon/x HTTP/1.1"), None);
+        assert_eq!(parse_path("GET /10 HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn echoes_the_message_after_the_delay() {
+        with_server(10, |url| async move {
+            let client = Client::new();
+            let start = Instant::now();
+            let response = client.get(&format!("{url}/100/request-1")).await.unwrap();
+            assert!(start.elapsed() >= Duration::from_millis(100));
+            assert_eq!(response.status, 200);
+            assert_eq!(response.text(), "request-1");
+            let response = client.get(&format!("{url}/not-a-delay")).await.unwrap();
+            assert_eq!(response.status, 400);
+        });
+    }
+
+    #[test]
+    fn limits_the_concurrent_connections() {
+        let elapsed = |concurrency| {
+            let elapsed = Rc::new(RefCell::new(Duration::ZERO));
+            let result = elapsed.clone();
+            with_server(concurrency, move |url| async move {
+                let (a, b) = (Client::new(), Client::new());
+                let start = Instant::now();
+                let (first, second) = join!(
+                    a.get(&format!("{url}/150/a")).send(),
+                    b.get(&format!("{url}/150/b")).send()
+                );
+                *result.borrow_mut() = start.elapsed();
+                assert_eq!(first.unwrap().text(), "a");
+                assert_eq!(second.unwrap().text(), "b");
+            });
+            elapsed.take()
+        };
+        assert!(elapsed(2) < Duration::from_millis(300));
+        assert!(elapsed(1) >= Duration::from_millis(300));
+    }
+}
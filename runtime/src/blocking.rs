@@ -0,0 +1,303 @@
+//! Running blocking code (file system calls, DNS lookups, heavy computations)
+//! on a pool of threads, so it doesn't stall the executor.
+//!
+//! ```text
+//! let contents = runtime::spawn_blocking(|| std::fs::read_to_string("config.toml"))
+//!     .await??;
+//! ```
+//!
+//! A pool starts without threads. A job is handed to an idle thread, if
+//! there is none a new thread is started, up to `max_threads`. After that,
+//! jobs are queued, up to `queue_limit`. Threads that were idle for
+//! `keep_alive` exit.
+use crate::sync::oneshot;
+use std::{
+    any::Any,
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static POOL: OnceLock<BlockingPool> = OnceLock::new();
+
+/// Run `f` on the default `BlockingPool`
+pub fn spawn_blocking<T, F>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    POOL.get_or_init(BlockingPool::new).spawn(f)
+}
+
+/// Threads for blocking jobs, see the module docs. Clones share the threads.
+#[derive(Clone)]
+pub struct BlockingPool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    // Signalled when a job is queued for an idle thread
+    job_queued: Condvar,
+    max_threads: usize,
+    keep_alive: Duration,
+    queue_limit: usize,
+    thread_name: String,
+}
+
+struct State {
+    queue: VecDeque<Job>,
+    threads: usize,
+    // Threads waiting for a job, including the ones that were just woken
+    idle: usize,
+    next_thread_id: usize,
+}
+
+/// Awaits the result of a job. Dropping it doesn't stop the job.
+pub struct JoinHandle<T> {
+    rx: oneshot::Receiver<Result<T, JoinError>>,
+}
+
+/// The job didn't return a value
+pub struct JoinError {
+    repr: Repr,
+}
+
+enum Repr {
+    // The lock only makes the error `Sync`, like other errors are
+    Panicked(Mutex<Box<dyn Any + Send>>),
+    // The queue was full
+    Rejected,
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingPool {
+    /// A pool with up to 64 threads named `blocking-<n>` that exit after 10
+    /// seconds without work and a queue of up to 1024 jobs
+    pub fn new() -> Self {
+        BlockingPool {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    queue: VecDeque::new(),
+                    threads: 0,
+                    idle: 0,
+                    next_thread_id: 0,
+                }),
+                job_queued: Condvar::new(),
+                max_threads: 64,
+                keep_alive: Duration::from_secs(10),
+                queue_limit: 1024,
+                thread_name: "blocking".to_string(),
+            }),
+        }
+    }
+
+    /// Panics if `max` is `0`. Like the other settings, this must be set
+    /// before the first job is spawned.
+    pub fn max_threads(self, max: usize) -> Self {
+        assert!(max > 0, "a blocking pool needs at least one thread");
+        self.configure(|inner| inner.max_threads = max)
+    }
+
+    /// How long a thread waits for a new job before it exits
+    pub fn keep_alive(self, keep_alive: Duration) -> Self {
+        self.configure(|inner| inner.keep_alive = keep_alive)
+    }
+
+    /// How many jobs can wait for a thread, further jobs are rejected
+    pub fn queue_limit(self, limit: usize) -> Self {
+        self.configure(|inner| inner.queue_limit = limit)
+    }
+
+    /// Threads are named `<name>-<n>`
+    pub fn thread_name(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.configure(|inner| inner.thread_name = name)
+    }
+
+    fn configure(mut self, f: impl FnOnce(&mut Inner)) -> Self {
+        f(Arc::get_mut(&mut self.inner)
+            .expect("a blocking pool can't be configured once it is shared"));
+        self
+    }
+
+    /// Run `f` on one of the pool's threads. If the job can't be queued, the
+    /// handle resolves to an error right away. A panic in `f` is caught and
+    /// returned as an error, the thread keeps running.
+    pub fn spawn<T, F>(&self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job: Job = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| JoinError {
+                repr: Repr::Panicked(Mutex::new(payload)),
+            });
+            // Nobody waits for the result if the handle was dropped
+            let _ = tx.send(result);
+        });
+        // A rejected job is dropped with its sender, the handle reports that
+        let _ = self.inner.submit(job);
+        JoinHandle { rx }
+    }
+
+    /// Number of running threads, busy or idle
+    pub fn threads(&self) -> usize {
+        self.inner.state.lock().unwrap().threads
+    }
+
+    /// Number of jobs waiting for a thread
+    pub fn queued(&self) -> usize {
+        self.inner.state.lock().unwrap().queue.len()
+    }
+}
+
+impl Inner {
+    fn submit(self: &Arc<Self>, job: Job) -> Result<(), Job> {
+        let mut state = self.state.lock().unwrap();
+        if state.queue.len() < state.idle {
+            state.queue.push_back(job);
+            self.job_queued.notify_one();
+        } else if state.threads < self.max_threads {
+            let name = format!("{}-{}", self.thread_name, state.next_thread_id);
+            state.next_thread_id += 1;
+            state.threads += 1;
+            let inner = self.clone();
+            // The new thread takes the job along, so it doesn't count as queued
+            let spawned = thread::Builder::new()
+                .name(name)
+                .spawn(move || inner.run_worker(job));
+            if spawned.is_err() {
+                // The job was dropped with the closure, which rejects it
+                state.threads -= 1;
+            }
+        } else if state.queue.len() < self.queue_limit {
+            state.queue.push_back(job);
+        } else {
+            return Err(job);
+        }
+        Ok(())
+    }
+
+    fn run_worker(&self, first: Job) {
+        first();
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock().unwrap();
+                continue;
+            }
+            state.idle += 1;
+            let (guard, timeout) = self
+                .job_queued
+                .wait_timeout(state, self.keep_alive)
+                .unwrap();
+            state = guard;
+            state.idle -= 1;
+            if timeout.timed_out() && state.queue.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match Pin::new(&mut self.rx).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            // Every job that runs sends its result, even if it panicked
+            Poll::Ready(Err(_)) => Poll::Ready(Err(JoinError {
+                repr: Repr::Rejected,
+            })),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl JoinError {
+    pub fn is_panic(&self) -> bool {
+        matches!(self.repr, Repr::Panicked(_))
+    }
+
+    /// The job was not run because the pool's queue was full (or no thread
+    /// could be started)
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.repr, Repr::Rejected)
+    }
+
+    /// The panic payload, e.g. to resume the panic with
+    /// `std::panic::resume_unwind`. Panics if the job didn't panic.
+    pub fn into_panic(self) -> Box<dyn Any + Send> {
+        match self.repr {
+            Repr::Panicked(payload) => payload.into_inner().unwrap(),
+            Repr::Rejected => panic!("into_panic called on a JoinError that is not a panic"),
+        }
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinHandle").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for BlockingPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockingPool")
+            .field("max_threads", &self.inner.max_threads)
+            .field("keep_alive", &self.inner.keep_alive)
+            .field("queue_limit", &self.inner.queue_limit)
+            .field("thread_name", &self.inner.thread_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repr {
+            Repr::Panicked(_) => f.write_str("JoinError::Panicked(..)"),
+            Repr::Rejected => f.write_str("JoinError::Rejected"),
+        }
+    }
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.repr {
+            Repr::Panicked(payload) => {
+                let payload = payload.lock().unwrap();
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str));
+                match message {
+                    Some(message) => write!(f, "blocking job panicked: {message}"),
+                    None => f.write_str("blocking job panicked"),
+                }
+            }
+            Repr::Rejected => f.write_str("blocking job rejected, the queue is full"),
+        }
+    }
+}
+
+impl Error for JoinError {}
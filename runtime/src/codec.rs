@@ -0,0 +1,63 @@
+//! Turning a byte stream into frames and back: `Decoder` and `Encoder`
+//! define the framing, `Framed` applies it to an `AsyncRead + AsyncWrite`
+//! and is a `Stream` of the decoded frames and a `Sink` for frames to encode.
+//!
+//! ```text
+//! let mut frames = Framed::new(stream, LinesCodec::new());
+//! while let Some(line) = frames.next().await {
+//!     frames.send(line?.to_uppercase()).await?;
+//! }
+//! ```
+//!
+//! `FramedRead` and `FramedWrite` do only one of both, e.g. for the halves
+//! of `io::split`.
+mod bytes_codec;
+mod framed;
+mod framed_read;
+mod framed_write;
+mod length_delimited;
+mod lines_codec;
+
+pub use bytes_codec::BytesCodec;
+pub use framed::Framed;
+pub use framed_read::FramedRead;
+pub use framed_write::FramedWrite;
+pub use length_delimited::LengthDelimitedCodec;
+pub use lines_codec::{LinesCodec, LinesCodecError};
+
+use std::io;
+
+/// Cuts frames off the front of a buffer of received bytes
+pub trait Decoder {
+    type Item;
+    /// Reading the bytes can fail with an IO error, so the error type must
+    /// be able to hold one
+    type Error: From<io::Error>;
+
+    /// Remove the next frame from `src`. `Ok(None)` if `src` doesn't hold a
+    /// whole frame yet, it is called again once more bytes arrived.
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<Self::Item>, Self::Error>;
+
+    /// Called when the stream ended, until it returns `Ok(None)`. By default
+    /// the remaining bytes must be whole frames.
+    fn decode_eof(&mut self, src: &mut Vec<u8>) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended within a frame",
+            )
+            .into()),
+        }
+    }
+}
+
+/// Turns values into bytes, appended to a buffer
+pub trait Encoder<Item> {
+    /// Writing the frames can fail with an IO error, so the error type must
+    /// be able to hold one
+    type Error: From<io::Error>;
+
+    fn encode(&mut self, item: Item, dst: &mut Vec<u8>) -> Result<(), Self::Error>;
+}
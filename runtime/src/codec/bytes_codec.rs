@@ -0,0 +1,43 @@
+use super::{Decoder, Encoder};
+use std::{io, mem};
+
+/// No framing at all: every chunk of received bytes is a frame and frames
+/// are written as they are
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytesCodec;
+
+impl BytesCodec {
+    pub fn new() -> Self {
+        BytesCodec
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(mem::take(src)))
+    }
+}
+
+impl Encoder<Vec<u8>> for BytesCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+impl Encoder<&[u8]> for BytesCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(item);
+        Ok(())
+    }
+}
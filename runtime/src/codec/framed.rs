@@ -0,0 +1,249 @@
+use super::{Decoder, Encoder};
+use crate::{
+    io::{AsyncRead, AsyncWrite},
+    sink::Sink,
+    stream::Stream,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+// Bytes read from the IO object at once
+const READ_CHUNK: usize = 8 * 1024;
+// `poll_ready` waits for the buffer to be written once it holds this much
+const BACKPRESSURE_BOUNDARY: usize = 8 * 1024;
+
+/// A `Stream` of the frames decoded from `io` and a `Sink` of frames that
+/// are encoded and written to `io`. A decoding error ends the stream.
+pub struct Framed<T, C> {
+    io: Pin<Box<T>>,
+    codec: C,
+    read: ReadState,
+    write: WriteState,
+}
+
+// Only the boxed IO object is pinned
+impl<T, C> Unpin for Framed<T, C> {}
+
+/// The read half of the framing, shared by `Framed` and `FramedRead`
+pub(super) struct ReadState {
+    buffer: Vec<u8>,
+    // New bytes arrived since the decoder last asked for more
+    readable: bool,
+    eof: bool,
+    // The stream ended or failed, no more frames
+    done: bool,
+}
+
+/// The write half of the framing, shared by `Framed` and `FramedWrite`
+pub(super) struct WriteState {
+    buffer: Vec<u8>,
+}
+
+impl<T, C> Framed<T, C> {
+    pub fn new(io: T, codec: C) -> Self {
+        Framed {
+            io: Box::pin(io),
+            codec,
+            read: ReadState::new(),
+            write: WriteState::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Bytes read but not decoded yet
+    pub fn read_buffer(&self) -> &[u8] {
+        &self.read.buffer
+    }
+
+    /// Frames encoded but not written yet
+    pub fn write_buffer(&self) -> &[u8] {
+        &self.write.buffer
+    }
+}
+
+impl<T: Unpin, C> Framed<T, C> {
+    /// Reading or writing directly interleaves with the frames
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Buffered bytes of both directions are lost
+    pub fn into_inner(self) -> T {
+        *Pin::into_inner(self.io)
+    }
+}
+
+impl<T: AsyncRead, C: Decoder> Stream for Framed<T, C> {
+    type Item = Result<C::Item, C::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.read.poll_next(cx, this.io.as_mut(), &mut this.codec)
+    }
+}
+
+impl<T: AsyncWrite, C: Encoder<Item>, Item> Sink<Item> for Framed<T, C> {
+    type Error = C::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), C::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.write.poll_ready(cx, this.io.as_mut()))?))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), C::Error> {
+        let this = self.get_mut();
+        this.codec.encode(item, &mut this.write.buffer)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), C::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.write.poll_flush(cx, this.io.as_mut()))?))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), C::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.write.poll_close(cx, this.io.as_mut()))?))
+    }
+}
+
+impl ReadState {
+    pub(super) fn new() -> Self {
+        ReadState {
+            buffer: Vec::new(),
+            readable: false,
+            eof: false,
+            done: false,
+        }
+    }
+
+    pub(super) fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub(super) fn poll_next<T, D>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut io: Pin<&mut T>,
+        decoder: &mut D,
+    ) -> Poll<Option<Result<D::Item, D::Error>>>
+    where
+        T: AsyncRead + ?Sized,
+        D: Decoder,
+    {
+        loop {
+            if self.done {
+                return Poll::Ready(None);
+            }
+            if self.readable {
+                let frame = if self.eof {
+                    decoder.decode_eof(&mut self.buffer)
+                } else {
+                    decoder.decode(&mut self.buffer)
+                };
+                match frame {
+                    Ok(Some(frame)) => return Poll::Ready(Some(Ok(frame))),
+                    Ok(None) if self.eof => self.done = true,
+                    Ok(None) => self.readable = false,
+                    Err(e) => {
+                        self.done = true;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                continue;
+            }
+            let mut chunk = [0u8; READ_CHUNK];
+            match ready!(io.as_mut().poll_read(cx, &mut chunk)) {
+                Ok(0) => self.eof = true,
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+            }
+            self.readable = true;
+        }
+    }
+}
+
+impl WriteState {
+    pub(super) fn new() -> Self {
+        WriteState { buffer: Vec::new() }
+    }
+
+    pub(super) fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub(super) fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    pub(super) fn poll_ready<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        io: Pin<&mut T>,
+    ) -> Poll<io::Result<()>>
+    where
+        T: AsyncWrite + ?Sized,
+    {
+        if self.buffer.len() >= BACKPRESSURE_BOUNDARY {
+            ready!(self.poll_write_buffer(cx, io))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    pub(super) fn poll_flush<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut io: Pin<&mut T>,
+    ) -> Poll<io::Result<()>>
+    where
+        T: AsyncWrite + ?Sized,
+    {
+        ready!(self.poll_write_buffer(cx, io.as_mut()))?;
+        io.poll_flush(cx)
+    }
+
+    pub(super) fn poll_close<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut io: Pin<&mut T>,
+    ) -> Poll<io::Result<()>>
+    where
+        T: AsyncWrite + ?Sized,
+    {
+        ready!(self.poll_write_buffer(cx, io.as_mut()))?;
+        io.poll_close(cx)
+    }
+
+    fn poll_write_buffer<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut io: Pin<&mut T>,
+    ) -> Poll<io::Result<()>>
+    where
+        T: AsyncWrite + ?Sized,
+    {
+        while !self.buffer.is_empty() {
+            match ready!(io.as_mut().poll_write(cx, &self.buffer))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => drop(self.buffer.drain(..n)),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,66 @@
+use super::{Decoder, framed::ReadState};
+use crate::{io::AsyncRead, stream::Stream};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A `Stream` of the frames decoded from `io`. A decoding error ends the
+/// stream.
+pub struct FramedRead<T, D> {
+    io: Pin<Box<T>>,
+    decoder: D,
+    state: ReadState,
+}
+
+// Only the boxed IO object is pinned
+impl<T, D> Unpin for FramedRead<T, D> {}
+
+impl<T, D> FramedRead<T, D> {
+    pub fn new(io: T, decoder: D) -> Self {
+        FramedRead {
+            io: Box::pin(io),
+            decoder,
+            state: ReadState::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    pub fn decoder(&self) -> &D {
+        &self.decoder
+    }
+
+    pub fn decoder_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// Bytes read but not decoded yet
+    pub fn read_buffer(&self) -> &[u8] {
+        self.state.buffer()
+    }
+}
+
+impl<T: Unpin, D> FramedRead<T, D> {
+    /// Reading directly skips ahead of the buffered bytes
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Bytes that were read but not decoded yet are lost
+    pub fn into_inner(self) -> T {
+        *Pin::into_inner(self.io)
+    }
+}
+
+impl<T: AsyncRead, D: Decoder> Stream for FramedRead<T, D> {
+    type Item = Result<D::Item, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.state
+            .poll_next(cx, this.io.as_mut(), &mut this.decoder)
+    }
+}
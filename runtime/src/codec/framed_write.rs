@@ -0,0 +1,81 @@
+use super::{Encoder, framed::WriteState};
+use crate::{io::AsyncWrite, sink::Sink};
+use std::{
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+/// A `Sink` of values that are encoded and written to `io`. Frames are
+/// buffered until the sink is flushed or the buffer gets too big.
+pub struct FramedWrite<T, E> {
+    io: Pin<Box<T>>,
+    encoder: E,
+    state: WriteState,
+}
+
+// Only the boxed IO object is pinned
+impl<T, E> Unpin for FramedWrite<T, E> {}
+
+impl<T, E> FramedWrite<T, E> {
+    pub fn new(io: T, encoder: E) -> Self {
+        FramedWrite {
+            io: Box::pin(io),
+            encoder,
+            state: WriteState::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    pub fn encoder(&self) -> &E {
+        &self.encoder
+    }
+
+    pub fn encoder_mut(&mut self) -> &mut E {
+        &mut self.encoder
+    }
+
+    /// Frames encoded but not written yet
+    pub fn write_buffer(&self) -> &[u8] {
+        self.state.buffer()
+    }
+}
+
+impl<T: Unpin, E> FramedWrite<T, E> {
+    /// Writing directly can interleave with the frames
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Frames that were not flushed yet are lost
+    pub fn into_inner(self) -> T {
+        *Pin::into_inner(self.io)
+    }
+}
+
+impl<T: AsyncWrite, E: Encoder<Item>, Item> Sink<Item> for FramedWrite<T, E> {
+    type Error = E::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.state.poll_ready(cx, this.io.as_mut()))?))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), E::Error> {
+        let this = self.get_mut();
+        this.encoder.encode(item, this.state.buffer_mut())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.state.poll_flush(cx, this.io.as_mut()))?))
+    }
+
+    /// Flushes and closes `io`, e.g. shuts a connection down for writing
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E::Error>> {
+        let this = self.get_mut();
+        Poll::Ready(Ok(ready!(this.state.poll_close(cx, this.io.as_mut()))?))
+    }
+}
@@ -0,0 +1,150 @@
+use super::{Decoder, Encoder};
+use std::io;
+
+/// Frames prefixed with their length. By default the length is a 4 byte
+/// big endian integer and frames can be up to 8 MiB:
+///
+/// ```text
+/// +---- len: u32 ----+---------- payload ----------+
+/// | 0x00 00 00 0B    |  Hello world                |
+/// +------------------+-----------------------------+
+/// ```
+///
+/// The header and the limit are configured with the chainable setters:
+///
+/// ```text
+/// let codec = LengthDelimitedCodec::new()
+///     .length_field_length(2)
+///     .little_endian()
+///     .max_frame_length(1024);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LengthDelimitedCodec {
+    length_field_length: usize,
+    big_endian: bool,
+    max_frame_length: usize,
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> Self {
+        LengthDelimitedCodec {
+            length_field_length: 4,
+            big_endian: true,
+            max_frame_length: 8 * 1024 * 1024,
+        }
+    }
+}
+
+impl LengthDelimitedCodec {
+    pub fn new() -> Self {
+        LengthDelimitedCodec::default()
+    }
+
+    /// Width of the length header in bytes, panics if not between 1 and 8
+    pub fn length_field_length(mut self, length: usize) -> Self {
+        assert!(
+            (1..=8).contains(&length),
+            "length field length must be between 1 and 8 bytes"
+        );
+        self.length_field_length = length;
+        self
+    }
+
+    pub fn big_endian(mut self) -> Self {
+        self.big_endian = true;
+        self
+    }
+
+    pub fn little_endian(mut self) -> Self {
+        self.big_endian = false;
+        self
+    }
+
+    /// Longer frames are rejected when decoding and when encoding, so a peer
+    /// can't make us buffer arbitrary amounts of data
+    pub fn max_frame_length(mut self, length: usize) -> Self {
+        self.max_frame_length = length;
+        self
+    }
+
+    fn read_length(&self, header: &[u8]) -> u64 {
+        let mut bytes = [0u8; 8];
+        if self.big_endian {
+            bytes[8 - header.len()..].copy_from_slice(header);
+            u64::from_be_bytes(bytes)
+        } else {
+            bytes[..header.len()].copy_from_slice(header);
+            u64::from_le_bytes(bytes)
+        }
+    }
+
+    fn write_length(&self, length: u64, dst: &mut Vec<u8>) {
+        let width = self.length_field_length;
+        if self.big_endian {
+            dst.extend_from_slice(&length.to_be_bytes()[8 - width..]);
+        } else {
+            dst.extend_from_slice(&length.to_le_bytes()[..width]);
+        }
+    }
+
+    fn frame_too_big(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame larger than {} bytes", self.max_frame_length),
+        )
+    }
+}
+
+impl Decoder for LengthDelimitedCodec {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        let header = self.length_field_length;
+        if src.len() < header {
+            return Ok(None);
+        }
+        let length = self.read_length(&src[..header]);
+        if length > self.max_frame_length as u64 {
+            return Err(self.frame_too_big());
+        }
+        let end = header + length as usize;
+        if src.len() < end {
+            // Make room for the rest of the frame at once
+            src.reserve(end - src.len());
+            return Ok(None);
+        }
+        let frame = src[header..end].to_vec();
+        src.drain(..end);
+        Ok(Some(frame))
+    }
+}
+
+impl Encoder<&[u8]> for LengthDelimitedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        if item.len() > self.max_frame_length {
+            return Err(self.frame_too_big());
+        }
+        let width = self.length_field_length;
+        if width < 8 && (item.len() as u64) >= 1 << (8 * width) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("frame length doesn't fit into {width} bytes"),
+            ));
+        }
+        dst.reserve(self.length_field_length + item.len());
+        self.write_length(item.len() as u64, dst);
+        dst.extend_from_slice(item);
+        Ok(())
+    }
+}
+
+impl Encoder<Vec<u8>> for LengthDelimitedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut Vec<u8>) -> io::Result<()> {
+        self.encode(item.as_slice(), dst)
+    }
+}
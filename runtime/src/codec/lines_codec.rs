@@ -0,0 +1,126 @@
+use super::{Decoder, Encoder};
+use std::{error::Error, fmt, io};
+
+/// Lines of UTF-8 text. Decoded lines don't include their `\n` or `\r\n`,
+/// encoded ones get a `\n` appended.
+#[derive(Debug, Clone, Default)]
+pub struct LinesCodec {
+    // Where to continue looking for the `\n`, the bytes before were checked
+    next_index: usize,
+    max_length: Option<usize>,
+}
+
+#[derive(Debug)]
+pub enum LinesCodecError {
+    /// A line is longer than the configured maximum
+    MaxLineLengthExceeded,
+    Io(io::Error),
+}
+
+impl LinesCodec {
+    pub fn new() -> Self {
+        LinesCodec::default()
+    }
+
+    /// Fail instead of buffering lines longer than `max_length` bytes
+    /// without their line ending, e.g. to cap what a peer can make us buffer
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        LinesCodec {
+            next_index: 0,
+            max_length: Some(max_length),
+        }
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    fn take_line(
+        &mut self,
+        src: &mut Vec<u8>,
+        end: usize,
+        skip: usize,
+    ) -> Result<String, LinesCodecError> {
+        self.next_index = 0;
+        let mut line: Vec<u8> = src.drain(..end + skip).take(end).collect();
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        String::from_utf8(line).map_err(|_| {
+            LinesCodecError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "line is not UTF-8",
+            ))
+        })
+    }
+}
+
+impl Decoder for LinesCodec {
+    type Item = String;
+    type Error = LinesCodecError;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<String>, LinesCodecError> {
+        let newline = src[self.next_index..].iter().position(|b| *b == b'\n');
+        let Some(offset) = newline else {
+            self.next_index = src.len();
+            // `\r` could still turn out to be part of the line ending
+            let length = src.strip_suffix(b"\r").unwrap_or(src).len();
+            if self.max_length.is_some_and(|max| length > max) {
+                return Err(LinesCodecError::MaxLineLengthExceeded);
+            }
+            return Ok(None);
+        };
+        let end = self.next_index + offset;
+        let length = src[..end].strip_suffix(b"\r").unwrap_or(&src[..end]).len();
+        if self.max_length.is_some_and(|max| length > max) {
+            return Err(LinesCodecError::MaxLineLengthExceeded);
+        }
+        self.take_line(src, end, 1).map(Some)
+    }
+
+    /// A last line without line ending is still a line
+    fn decode_eof(&mut self, src: &mut Vec<u8>) -> Result<Option<String>, LinesCodecError> {
+        match self.decode(src)? {
+            Some(line) => Ok(Some(line)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                let end = src.len();
+                self.take_line(src, end, 0).map(Some)
+            }
+        }
+    }
+}
+
+impl<T: AsRef<str>> Encoder<T> for LinesCodec {
+    type Error = LinesCodecError;
+
+    fn encode(&mut self, line: T, dst: &mut Vec<u8>) -> Result<(), LinesCodecError> {
+        dst.extend_from_slice(line.as_ref().as_bytes());
+        dst.push(b'\n');
+        Ok(())
+    }
+}
+
+impl fmt::Display for LinesCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinesCodecError::MaxLineLengthExceeded => f.write_str("max line length exceeded"),
+            LinesCodecError::Io(e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl Error for LinesCodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LinesCodecError::MaxLineLengthExceeded => None,
+            LinesCodecError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for LinesCodecError {
+    fn from(e: io::Error) -> Self {
+        LinesCodecError::Io(e)
+    }
+}
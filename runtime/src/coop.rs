@@ -0,0 +1,90 @@
+//! Cooperative scheduling. A task that keeps finding its resources ready
+//! (a socket with lots of data, a full channel) would never return
+//! `Pending` and starve all other tasks. So every time the executor polls a
+//! task, the task gets a budget of operations on resources. Once it is used
+//! up, resources return `Pending` and wake the task right away, which puts
+//! it at the back of the ready queue.
+use std::{
+    cell::Cell,
+    task::{Context, Poll, ready},
+};
+
+// Operations per poll of a task
+const BUDGET: u8 = 128;
+
+thread_local! {
+    // The remaining budget of the task being polled, `None` outside of a
+    // task or within `unconstrained`
+    static REMAINING: Cell<Option<u8>> = const { Cell::new(None) };
+}
+
+/// Run `f`, which polls a task, with a fresh budget
+pub(crate) fn budget<R>(f: impl FnOnce() -> R) -> R {
+    with_budget(Some(BUDGET), f)
+}
+
+/// Run `f` without a budget
+pub(crate) fn unconstrained<R>(f: impl FnOnce() -> R) -> R {
+    with_budget(None, f)
+}
+
+fn with_budget<R>(budget: Option<u8>, f: impl FnOnce() -> R) -> R {
+    // Restores the outer budget, even if `f` panics
+    struct Reset(Option<u8>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            REMAINING.set(self.0);
+        }
+    }
+
+    let _reset = Reset(REMAINING.replace(budget));
+    f()
+}
+
+/// Poll a resource with `f`, an operation that completes takes one unit of
+/// the budget. Pending without calling `f` if the budget is used up.
+pub(crate) fn poll_budgeted<T>(
+    cx: &mut Context<'_>,
+    f: impl FnOnce(&mut Context<'_>) -> Poll<T>,
+) -> Poll<T> {
+    let consumed = ready!(poll_proceed(cx));
+    let poll = f(cx);
+    if poll.is_ready() {
+        consumed.made_progress();
+    }
+    poll
+}
+
+fn poll_proceed(cx: &mut Context<'_>) -> Poll<RestoreOnPending> {
+    match REMAINING.get() {
+        None => Poll::Ready(RestoreOnPending(false)),
+        Some(0) => {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+        Some(remaining) => {
+            REMAINING.set(Some(remaining - 1));
+            Poll::Ready(RestoreOnPending(true))
+        }
+    }
+}
+
+/// A unit of budget that is given back if the operation didn't complete
+struct RestoreOnPending(bool);
+
+impl RestoreOnPending {
+    fn made_progress(mut self) {
+        self.0 = false;
+    }
+}
+
+impl Drop for RestoreOnPending {
+    fn drop(&mut self) {
+        if self.0
+            && let Some(remaining) = REMAINING.get()
+        {
+            REMAINING.set(Some(remaining + 1));
+        }
+    }
+}
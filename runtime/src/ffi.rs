@@ -0,0 +1,59 @@
+pub const IN_NONBLOCK: i32 = 0o4000;
+pub const IN_CLOEXEC: i32 = 0o2000000;
+
+pub const IN_ACCESS: u32 = 0x1;
+pub const IN_MODIFY: u32 = 0x2;
+pub const IN_ATTRIB: u32 = 0x4;
+pub const IN_CLOSE_WRITE: u32 = 0x8;
+pub const IN_CLOSE_NOWRITE: u32 = 0x10;
+pub const IN_OPEN: u32 = 0x20;
+pub const IN_MOVED_FROM: u32 = 0x40;
+pub const IN_MOVED_TO: u32 = 0x80;
+pub const IN_CREATE: u32 = 0x100;
+pub const IN_DELETE: u32 = 0x200;
+pub const IN_DELETE_SELF: u32 = 0x400;
+pub const IN_MOVE_SELF: u32 = 0x800;
+pub const IN_Q_OVERFLOW: u32 = 0x4000;
+pub const IN_IGNORED: u32 = 0x8000;
+pub const IN_ISDIR: u32 = 0x4000_0000;
+
+#[link(name = "c")]
+unsafe extern "C" {
+    pub fn inotify_init1(flags: i32) -> i32;
+    pub fn inotify_add_watch(fd: i32, pathname: *const std::ffi::c_char, mask: u32) -> i32;
+}
+
+/// Header of a `struct inotify_event`, it is followed by `len` bytes holding
+/// the NUL padded name of the file inside a watched directory.
+pub const INOTIFY_EVENT_HEADER: usize = 16;
+
+pub const AF_INET: i32 = 2;
+pub const AF_INET6: i32 = 10;
+pub const SOCK_STREAM: i32 = 1;
+pub const SOCK_NONBLOCK: i32 = 0o4000;
+pub const SOCK_CLOEXEC: i32 = 0o2000000;
+pub const EINPROGRESS: i32 = 115;
+
+#[link(name = "c")]
+unsafe extern "C" {
+    pub fn socket(domain: i32, ty: i32, protocol: i32) -> i32;
+    pub fn connect(fd: i32, addr: *const u8, len: u32) -> i32;
+}
+
+#[repr(C)]
+pub struct SockAddrIn {
+    pub sin_family: u16,
+    // Port and address are in network byte order
+    pub sin_port: u16,
+    pub sin_addr: [u8; 4],
+    pub sin_zero: [u8; 8],
+}
+
+#[repr(C)]
+pub struct SockAddrIn6 {
+    pub sin6_family: u16,
+    pub sin6_port: u16,
+    pub sin6_flowinfo: u32,
+    pub sin6_addr: [u8; 16],
+    pub sin6_scope_id: u32,
+}
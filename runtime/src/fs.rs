@@ -0,0 +1,176 @@
+//! Asynchronous file system notifications built on inotify.
+use crate::{coop, ffi, io::AsyncFd, stream::Stream};
+use std::{
+    ffi::{CString, OsStr},
+    fs::File,
+    future::poll_fn,
+    io::{self, Read},
+    os::{
+        fd::FromRawFd,
+        unix::ffi::{OsStrExt, OsStringExt},
+    },
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+pub use crate::ffi::{
+    IN_ACCESS, IN_ATTRIB, IN_CLOSE_NOWRITE, IN_CLOSE_WRITE, IN_CREATE, IN_DELETE, IN_DELETE_SELF,
+    IN_MODIFY, IN_MOVE_SELF, IN_MOVED_FROM, IN_MOVED_TO, IN_OPEN,
+};
+/// Both halves of a rename
+pub const IN_MOVE: u32 = IN_MOVED_FROM | IN_MOVED_TO;
+/// Everything that changes the content of a watched directory
+pub const IN_ALL_CHANGES: u32 = IN_CREATE | IN_MODIFY | IN_DELETE | IN_MOVE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchEventKind {
+    Created,
+    Modified,
+    Deleted,
+    /// A file was moved away, the matching `MovedTo` has the same cookie
+    MovedFrom,
+    MovedTo,
+    /// The watched path itself was moved, the watch follows it
+    MovedSelf,
+    Other,
+}
+
+/// A parsed `struct inotify_event`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchEvent {
+    pub kind: WatchEventKind,
+    /// File inside the watched directory, `None` if the watched path itself changed
+    pub name: Option<PathBuf>,
+    /// Connects the `MovedFrom` and `MovedTo` events of a single rename
+    pub cookie: u32,
+    pub is_dir: bool,
+    /// The raw `IN_*` flags of the event
+    pub mask: u32,
+}
+
+impl WatchEvent {
+    fn parse(mask: u32, cookie: u32, name: &[u8]) -> Self {
+        let kind = if mask & IN_CREATE != 0 {
+            WatchEventKind::Created
+        } else if mask & (IN_MODIFY | IN_CLOSE_WRITE | IN_ATTRIB) != 0 {
+            WatchEventKind::Modified
+        } else if mask & (IN_DELETE | IN_DELETE_SELF) != 0 {
+            WatchEventKind::Deleted
+        } else if mask & IN_MOVED_FROM != 0 {
+            WatchEventKind::MovedFrom
+        } else if mask & IN_MOVED_TO != 0 {
+            WatchEventKind::MovedTo
+        } else if mask & IN_MOVE_SELF != 0 {
+            WatchEventKind::MovedSelf
+        } else {
+            WatchEventKind::Other
+        };
+        // The name is padded with NUL bytes up to `len`
+        let name = name.split(|b| *b == 0).next().unwrap_or_default();
+        WatchEvent {
+            kind,
+            name: (!name.is_empty()).then(|| PathBuf::from(OsStr::from_bytes(name))),
+            cookie,
+            is_dir: mask & ffi::IN_ISDIR != 0,
+            mask,
+        }
+    }
+}
+
+/// Stream of the changes to a watched path, created by `watch`
+pub struct Watcher {
+    fd: AsyncFd<File>,
+    buffer: Vec<u8>,
+    // Range of `buffer` that holds events which were read but not yet returned
+    pos: usize,
+    len: usize,
+    done: bool,
+}
+
+/// Watch `path` (a file or a directory) for the events in `mask`,
+/// e.g. `IN_CREATE | IN_MODIFY` or `IN_ALL_CHANGES`.
+pub fn watch(path: impl AsRef<Path>, mask: u32) -> io::Result<Watcher> {
+    let path = CString::new(path.as_ref().as_os_str().to_owned().into_vec())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let fd = unsafe { ffi::inotify_init1(ffi::IN_NONBLOCK | ffi::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // From here on the fd is closed when `file` is dropped
+    let file = unsafe { File::from_raw_fd(fd) };
+    let res = unsafe { ffi::inotify_add_watch(fd, path.as_ptr(), mask) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(Watcher {
+        fd: AsyncFd::new(file)?,
+        buffer: vec![0u8; 4096],
+        pos: 0,
+        len: 0,
+        done: false,
+    })
+}
+
+impl Watcher {
+    /// Wait for the next event. Returns `None` once the watch is gone,
+    /// e.g. because the watched path was deleted.
+    pub async fn next(&mut self) -> Option<io::Result<WatchEvent>> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    pub fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<io::Result<WatchEvent>>> {
+        coop::poll_budgeted(cx, |cx| {
+            let this = &mut *self;
+            loop {
+                if this.done {
+                    return Poll::Ready(None);
+                }
+                if this.pos < this.len {
+                    let header = &this.buffer[this.pos..this.pos + ffi::INOTIFY_EVENT_HEADER];
+                    let field = |i: usize| u32::from_ne_bytes(header[i..i + 4].try_into().unwrap());
+                    let (mask, cookie, name_len) = (field(4), field(8), field(12) as usize);
+                    let name_start = this.pos + ffi::INOTIFY_EVENT_HEADER;
+                    let name = &this.buffer[name_start..name_start + name_len];
+                    this.pos = name_start + name_len;
+
+                    if mask & ffi::IN_Q_OVERFLOW != 0 {
+                        return Poll::Ready(Some(Err(io::Error::other(
+                            "inotify event queue overflowed, events were lost",
+                        ))));
+                    }
+                    if mask & ffi::IN_IGNORED != 0 {
+                        this.done = true;
+                        continue;
+                    }
+                    return Poll::Ready(Some(Ok(WatchEvent::parse(mask, cookie, name))));
+                }
+
+                let mut guard = match ready!(this.fd.poll_read_ready(cx)) {
+                    Ok(guard) => guard,
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                };
+                match guard.try_io(|fd| fd.get_ref().read(&mut this.buffer)) {
+                    Ok(Ok(n)) => {
+                        this.pos = 0;
+                        this.len = n;
+                    }
+                    Ok(Err(e)) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Ok(Err(e)) => return Poll::Ready(Some(Err(e))),
+                    Err(_would_block) => (),
+                }
+            }
+        })
+    }
+}
+
+impl Stream for Watcher {
+    type Item = io::Result<WatchEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Watcher::poll_next(self, cx)
+    }
+}
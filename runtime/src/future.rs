@@ -0,0 +1,184 @@
+//! Helpers for running several futures concurrently inside one task. The
+//! `join!`, `try_join!` and `select!` macros cover a fixed number of futures,
+//! the functions here work on any number of them.
+mod futures_unordered;
+
+pub use futures_unordered::FuturesUnordered;
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    future::{Future, poll_fn},
+    hash::{BuildHasher, Hasher},
+    pin::{Pin, pin},
+    task::{Context, Poll},
+};
+
+/// A future that keeps its output once it completed, so it can be polled
+/// together with others until all of them are done. Used by `join!`. The
+/// future is polled in place, so a `MaybeDone` must be pinned.
+pub enum MaybeDone<F: Future> {
+    Future(F),
+    Done(F::Output),
+    Gone,
+}
+
+impl<F: Future> MaybeDone<F> {
+    pub fn new(future: F) -> Self {
+        MaybeDone::Future(future)
+    }
+
+    /// Poll the future unless it completed already
+    pub fn poll_done(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // SAFETY: the future is never moved out, `set` drops it in place
+        let output = match unsafe { self.as_mut().get_unchecked_mut() } {
+            MaybeDone::Future(future) => {
+                std::task::ready!(unsafe { Pin::new_unchecked(future) }.poll(cx))
+            }
+            _ => return Poll::Ready(()),
+        };
+        self.set(MaybeDone::Done(output));
+        Poll::Ready(())
+    }
+
+    pub fn output(&self) -> Option<&F::Output> {
+        match self {
+            MaybeDone::Done(output) => Some(output),
+            _ => None,
+        }
+    }
+
+    pub fn take_output(self: Pin<&mut Self>) -> Option<F::Output> {
+        if !matches!(*self, MaybeDone::Done(_)) {
+            return None;
+        }
+        // SAFETY: only the output is moved, it was never pinned
+        match std::mem::replace(unsafe { self.get_unchecked_mut() }, MaybeDone::Gone) {
+            MaybeDone::Done(output) => Some(output),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<F, T, E> MaybeDone<F>
+where
+    F: Future<Output = Result<T, E>>,
+{
+    /// Take the output if the future failed
+    pub fn take_err(self: Pin<&mut Self>) -> Option<E> {
+        match self.output() {
+            Some(Err(_)) => self.take_output().and_then(Result::err),
+            _ => None,
+        }
+    }
+}
+
+/// Wait for all futures and return their outputs in the same order
+pub async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let futures: Box<[_]> = futures.into_iter().map(MaybeDone::new).collect();
+    let mut futures = Box::into_pin(futures);
+    poll_fn(|cx| {
+        let mut done = true;
+        for future in iter_pin_mut(futures.as_mut()) {
+            done &= future.poll_done(cx).is_ready();
+        }
+        if !done {
+            return Poll::Pending;
+        }
+        Poll::Ready(
+            iter_pin_mut(futures.as_mut())
+                .map(|future| future.take_output().unwrap())
+                .collect(),
+        )
+    })
+    .await
+}
+
+/// The elements of a pinned slice, each pinned
+fn iter_pin_mut<T>(slice: Pin<&mut [T]>) -> impl Iterator<Item = Pin<&mut T>> {
+    // SAFETY: the elements are pinned along with the slice and never moved out
+    unsafe { slice.get_unchecked_mut() }
+        .iter_mut()
+        .map(|item| unsafe { Pin::new_unchecked(item) })
+}
+
+/// Wait for the first of the futures to complete. Returns its output, its
+/// index and the futures that are still running, so they can be passed to
+/// `select_all` again. Panics if there are no futures.
+pub async fn select_all<F>(futures: impl IntoIterator<Item = F>) -> (F::Output, usize, Vec<F>)
+where
+    F: Future + Unpin,
+{
+    let mut futures: Vec<F> = futures.into_iter().collect();
+    assert!(!futures.is_empty(), "select_all needs at least one future");
+    let (output, index) = poll_fn(|cx| {
+        // Start at a random future, so one that is always ready can't starve the others
+        let start = random_index(futures.len());
+        for offset in 0..futures.len() {
+            let index = (start + offset) % futures.len();
+            if let Poll::Ready(output) = Pin::new(&mut futures[index]).poll(cx) {
+                return Poll::Ready((output, index));
+            }
+        }
+        Poll::Pending
+    })
+    .await;
+    futures.swap_remove(index);
+    (output, index, futures)
+}
+
+/// Wait for the first of both futures and drop the other one. If both are
+/// ready, a random one wins.
+pub async fn race<T>(a: impl Future<Output = T>, b: impl Future<Output = T>) -> T {
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    poll_fn(|cx| {
+        if random_index(2) == 0 {
+            if let Poll::Ready(output) = a.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            b.as_mut().poll(cx)
+        } else {
+            if let Poll::Ready(output) = b.as_mut().poll(cx) {
+                return Poll::Ready(output);
+            }
+            a.as_mut().poll(cx)
+        }
+    })
+    .await
+}
+
+thread_local! {
+    static RNG: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+}
+
+/// A random number in `0..n`, good enough to pick which future to poll first
+#[doc(hidden)]
+pub fn random_index(n: usize) -> usize {
+    if n < 2 {
+        return 0;
+    }
+    RNG.with(|rng| {
+        let x = xorshift(rng.get());
+        rng.set(x);
+        (x % n as u64) as usize
+    })
+}
+
+/// Make `random_index` on this thread repeat the same sequence for the
+/// same seed
+pub(crate) fn seed_random_index(seed: u64) {
+    RNG.set(seed_state(seed));
+}
+
+/// A valid `xorshift` state for any seed, it must not be zero
+pub(crate) fn seed_state(seed: u64) -> u64 {
+    (seed ^ 0x9e37_79b9_7f4a_7c15).max(1)
+}
+
+/// The next state of a xorshift64 generator, which is also its output
+pub(crate) fn xorshift(mut x: u64) -> u64 {
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
@@ -0,0 +1,182 @@
+use crate::stream::Stream;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::{Future, poll_fn},
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Wake, Waker},
+};
+
+/// A set of futures that are polled concurrently inside one task and yield
+/// their outputs in the order they complete.
+///
+/// Every future gets its own waker. Waking it queues just that future and
+/// wakes the task that polls the set, so a wake-up only polls the futures
+/// that can make progress instead of all of them.
+///
+/// ```text
+/// let mut requests = FuturesUnordered::new();
+/// requests.push(fetch(1));
+/// while let Some(response) = requests.next().await {
+///     // More futures can be pushed while iterating
+/// }
+/// ```
+pub struct FuturesUnordered<F> {
+    futures: HashMap<usize, Entry<F>>,
+    next_id: usize,
+    ready: Arc<ReadyQueue>,
+}
+
+struct Entry<F> {
+    future: Pin<Box<F>>,
+    waker: Waker,
+    child: Arc<ChildWaker>,
+}
+
+/// The futures that were woken and the task that polls the set
+#[derive(Default)]
+struct ReadyQueue {
+    queue: Mutex<VecDeque<usize>>,
+    parent: Mutex<Option<Waker>>,
+}
+
+struct ChildWaker {
+    id: usize,
+    // Set while the id is in the ready queue, so it's queued only once
+    queued: AtomicBool,
+    ready: Arc<ReadyQueue>,
+}
+
+impl Wake for ChildWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        if !self.queued.swap(true, Ordering::AcqRel) {
+            self.ready.queue.lock().unwrap().push_back(self.id);
+        }
+        let parent = self.ready.parent.lock().unwrap().clone();
+        if let Some(parent) = parent {
+            parent.wake();
+        }
+    }
+}
+
+impl<F: Future> FuturesUnordered<F> {
+    pub fn new() -> Self {
+        FuturesUnordered {
+            futures: HashMap::new(),
+            next_id: 0,
+            ready: Arc::new(ReadyQueue::default()),
+        }
+    }
+
+    /// Add a future, it's polled the next time the set is polled
+    pub fn push(&mut self, future: F) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let child = Arc::new(ChildWaker {
+            id,
+            queued: AtomicBool::new(true),
+            ready: self.ready.clone(),
+        });
+        self.futures.insert(
+            id,
+            Entry {
+                future: Box::pin(future),
+                waker: Waker::from(child.clone()),
+                child,
+            },
+        );
+        self.ready.queue.lock().unwrap().push_back(id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.futures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.futures.is_empty()
+    }
+
+    /// Wait for the next future to complete, `None` once the set is empty
+    pub async fn next(&mut self) -> Option<F::Output> {
+        poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    pub fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        {
+            let mut parent = self.ready.parent.lock().unwrap();
+            match &*parent {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => *parent = Some(cx.waker().clone()),
+            }
+        }
+        // A future that wakes itself right away must not keep us here forever
+        let mut budget = self.futures.len();
+        loop {
+            if self.futures.is_empty() {
+                return Poll::Ready(None);
+            }
+            let Some(id) = self.ready.queue.lock().unwrap().pop_front() else {
+                return Poll::Pending;
+            };
+            // Stale ids of futures that completed already are skipped
+            let Some(entry) = self.futures.get_mut(&id) else {
+                continue;
+            };
+            entry.child.queued.store(false, Ordering::Release);
+            let mut child_cx = Context::from_waker(&entry.waker);
+            if let Poll::Ready(output) = entry.future.as_mut().poll(&mut child_cx) {
+                self.futures.remove(&id);
+                return Poll::Ready(Some(output));
+            }
+            if budget == 0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            budget -= 1;
+        }
+    }
+}
+
+impl<F: Future> Stream for FuturesUnordered<F> {
+    type Item = F::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<F::Output>> {
+        FuturesUnordered::poll_next(self.get_mut(), cx)
+    }
+}
+
+impl<F: Future> Default for FuturesUnordered<F> {
+    fn default() -> Self {
+        FuturesUnordered::new()
+    }
+}
+
+impl<F: Future> FromIterator<F> for FuturesUnordered<F> {
+    fn from_iter<I: IntoIterator<Item = F>>(iter: I) -> Self {
+        let mut set = FuturesUnordered::new();
+        iter.into_iter().for_each(|future| set.push(future));
+        set
+    }
+}
+
+impl<F: Future> Extend<F> for FuturesUnordered<F> {
+    fn extend<I: IntoIterator<Item = F>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|future| self.push(future));
+    }
+}
+
+impl<F> fmt::Debug for FuturesUnordered<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FuturesUnordered")
+            .field("len", &self.futures.len())
+            .finish()
+    }
+}
@@ -0,0 +1,258 @@
+use super::{
+    Request, Response,
+    proto::{self, Connection, Framing, invalid_data},
+};
+use crate::net::{self, TcpStream};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::{Future, IntoFuture},
+    io,
+    pin::Pin,
+};
+
+/// HTTP/1.1 client that keeps connections alive and reuses them per host.
+///
+/// If a reused connection turns out to be closed by the server, idempotent
+/// requests (`GET`, `HEAD`, `PUT`, `DELETE`, ...) are retried once on a new
+/// connection, others like `POST` fail with the error.
+///
+/// A response body bigger than `max_body_size` fails the request, and so
+/// does a header with a line break in it.
+///
+/// ```text
+/// let client = Client::new();
+/// let response = client.get("http://localhost:8080/1000/hello").await?;
+/// println!("{} {}", response.status, response.text());
+/// ```
+pub struct Client {
+    // Idle keep-alive connections by `host:port`
+    idle: RefCell<HashMap<String, Vec<Connection>>>,
+    max_body_size: usize,
+}
+
+/// A request that is sent when it is awaited (or `send` is called)
+pub struct RequestBuilder<'a> {
+    client: &'a Client,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+struct Url {
+    host: String,
+    port: u16,
+    /// `host[:port]` as given, used for the `Host` header and the connection pool
+    authority: String,
+    path: String,
+}
+
+impl Url {
+    fn parse(url: &str) -> io::Result<Url> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only http:// urls are supported",
+            )
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(pos) => rest.split_at(pos),
+            None => (rest, "/"),
+        };
+        // `[::1]:8080` style IPv6 literals contain colons themselves
+        let port_sep = match authority.rfind(']') {
+            Some(bracket) => authority[bracket..].find(':').map(|pos| bracket + pos),
+            None => authority.rfind(':'),
+        };
+        let (host, port) = match port_sep {
+            Some(pos) => {
+                let port = authority[pos + 1..]
+                    .parse()
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid port"))?;
+                (&authority[..pos], port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "missing host"));
+        }
+        Ok(Url {
+            host: host
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            port,
+            authority: authority.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client {
+            idle: RefCell::default(),
+            max_body_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Largest response body in bytes, 16 MiB by default
+    pub fn max_body_size(mut self, max: usize) -> Self {
+        self.max_body_size = max;
+        self
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder<'_> {
+        self.request("GET", url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder<'_> {
+        self.request("POST", url)
+    }
+
+    pub fn request(&self, method: &str, url: &str) -> RequestBuilder<'_> {
+        RequestBuilder {
+            client: self,
+            method: method.to_string(),
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    async fn execute(&self, url: Url, request: Request) -> io::Result<Response> {
+        let start_line = format!("{} {} HTTP/1.1", request.method, request.path);
+        let message = proto::encode(&start_line, &request.headers, &request.body)?;
+        let pooled = self
+            .idle
+            .borrow_mut()
+            .get_mut(&url.authority)
+            .and_then(Vec::pop);
+        if let Some(connection) = pooled {
+            match self.exchange(connection, &url, &request, &message).await {
+                // The server may have closed the idle connection in the meantime,
+                // then we just try again on a fresh one. Only if sending the
+                // request twice does no harm, the server could have received
+                // (part of) it already.
+                Err(e) if is_stale(&e) && is_idempotent(&request.method) => (),
+                result => return result,
+            }
+        }
+        let addrs = net::lookup_host((url.host.clone(), url.port)).await?;
+        let stream = TcpStream::connect(addrs.as_slice()).await?;
+        self.exchange(Connection::new(stream), &url, &request, &message)
+            .await
+    }
+
+    async fn exchange(
+        &self,
+        mut connection: Connection,
+        url: &Url,
+        request: &Request,
+        message: &[u8],
+    ) -> io::Result<Response> {
+        connection.stream.write_all(message).await?;
+
+        let (head, version, status, reason) = loop {
+            let head = connection
+                .read_head(proto::MAX_HEAD_SIZE)
+                .await?
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            let mut parts = head.start_line.splitn(3, ' ');
+            let version = parts.next().unwrap_or_default().to_string();
+            let status: u16 = parts
+                .next()
+                .and_then(|status| status.parse().ok())
+                .ok_or_else(|| invalid_data("malformed status line"))?;
+            let reason = parts.next().unwrap_or_default().to_string();
+            // Skip interim responses like `100 Continue`
+            if !(100..200).contains(&status) {
+                break (head, version, status, reason);
+            }
+        };
+
+        let framing = if request.method == "HEAD" || status == 204 || status == 304 {
+            Framing::Empty
+        } else {
+            head.framing()?.unwrap_or(Framing::UntilClose)
+        };
+        let body = connection.read_body(framing, self.max_body_size).await?;
+        if framing != Framing::UntilClose && head.keep_alive(&version) {
+            self.idle
+                .borrow_mut()
+                .entry(url.authority.clone())
+                .or_default()
+                .push(connection);
+        }
+        Ok(Response {
+            status,
+            reason,
+            headers: head.headers,
+            body,
+        })
+    }
+}
+
+fn is_stale(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset | io::ErrorKind::BrokenPipe
+    )
+}
+
+fn is_idempotent(method: &str) -> bool {
+    matches!(
+        method,
+        "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS" | "TRACE"
+    )
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub async fn send(self) -> io::Result<Response> {
+        let url = Url::parse(&self.url)?;
+        let mut request = Request {
+            method: self.method,
+            path: url.path.clone(),
+            headers: self.headers,
+            body: self.body,
+        };
+        if request.header("Host").is_none() {
+            request
+                .headers
+                .insert(0, ("Host".to_string(), url.authority.clone()));
+        }
+        let has_body =
+            !request.body.is_empty() || matches!(request.method.as_str(), "POST" | "PUT");
+        if has_body && request.header("Content-Length").is_none() {
+            let length = request.body.len().to_string();
+            request.headers.push(("Content-Length".to_string(), length));
+        }
+        self.client.execute(url, request).await
+    }
+}
+
+impl<'a> IntoFuture for RequestBuilder<'a> {
+    type Output = io::Result<Response>;
+    type IntoFuture = Pin<Box<dyn Future<Output = io::Result<Response>> + 'a>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(self.send())
+    }
+}
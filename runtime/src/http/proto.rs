@@ -0,0 +1,256 @@
+//! The HTTP/1.1 wire format shared by the client and the server.
+use super::find_header;
+use crate::net::TcpStream;
+use std::{error::Error, fmt, io};
+
+/// Requests or responses with a bigger head are rejected, unless a server
+/// is configured otherwise. Also the limit for a single chunk size line.
+pub(crate) const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// The head or the body of a message exceeds the configured limit
+#[derive(Debug)]
+pub(crate) struct TooLarge(&'static str);
+
+/// How the end of a message body is determined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Framing {
+    Empty,
+    Length(usize),
+    Chunked,
+    /// The body ends when the peer closes the connection
+    UntilClose,
+}
+
+/// The start line and the headers of a message
+pub(crate) struct Head {
+    pub(crate) start_line: String,
+    pub(crate) headers: Vec<(String, String)>,
+}
+
+impl Head {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Framing of the body as announced by the headers, `None` if there is no
+    /// `Transfer-Encoding` or `Content-Length` header.
+    pub(crate) fn framing(&self) -> io::Result<Option<Framing>> {
+        if let Some(encoding) = find_header(&self.headers, "Transfer-Encoding") {
+            return match encoding.rsplit(',').next().map(str::trim) {
+                Some(last) if last.eq_ignore_ascii_case("chunked") => Ok(Some(Framing::Chunked)),
+                _ => Err(invalid_data("unsupported Transfer-Encoding")),
+            };
+        }
+        match find_header(&self.headers, "Content-Length") {
+            Some(length) => length
+                .trim()
+                .parse()
+                .map(|length| Some(Framing::Length(length)))
+                .map_err(|_| invalid_data("invalid Content-Length")),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the peer asked to keep the connection open after this message
+    pub(crate) fn keep_alive(&self, version: &str) -> bool {
+        match find_header(&self.headers, "Connection") {
+            Some(value) if value.eq_ignore_ascii_case("close") => false,
+            Some(value) if value.eq_ignore_ascii_case("keep-alive") => true,
+            _ => version == "HTTP/1.1",
+        }
+    }
+}
+
+pub(crate) fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg.to_string())
+}
+
+fn too_large(what: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, TooLarge(what))
+}
+
+/// Whether reading a message failed because it exceeded a limit
+pub(crate) fn is_too_large(e: &io::Error) -> bool {
+    e.get_ref().is_some_and(|inner| inner.is::<TooLarge>())
+}
+
+impl fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} too large", self.0)
+    }
+}
+
+impl Error for TooLarge {}
+
+/// A TCP connection with a read buffer, so a message can be parsed
+/// regardless of how it is split into packets.
+pub(crate) struct Connection {
+    pub(crate) stream: TcpStream,
+    buffer: Vec<u8>,
+}
+
+impl Connection {
+    pub(crate) fn new(stream: TcpStream) -> Self {
+        Connection {
+            stream,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Bytes that were received but not parsed yet, e.g. a pipelined request
+    pub(crate) fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Receive more bytes, returns `0` once the peer closed the connection.
+    /// Nothing is lost if the future is dropped before it completes.
+    pub(crate) async fn fill(&mut self) -> io::Result<usize> {
+        let mut chunk = [0u8; 4096];
+        let n = self.stream.read(&mut chunk).await?;
+        self.buffer.extend_from_slice(&chunk[..n]);
+        Ok(n)
+    }
+
+    /// Read the start line and the headers, which may take up to `max_size`
+    /// bytes. Returns `None` if the connection was closed before the first byte.
+    pub(crate) async fn read_head(&mut self, max_size: usize) -> io::Result<Option<Head>> {
+        let end = loop {
+            if let Some(pos) = self.buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                if pos > max_size {
+                    return Err(too_large("message head"));
+                }
+                break pos;
+            }
+            if self.buffer.len() > max_size {
+                return Err(too_large("message head"));
+            }
+            if self.fill().await? == 0 {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        };
+        let head: Vec<u8> = self.buffer.drain(..end + 4).collect();
+        let head =
+            std::str::from_utf8(&head[..end]).map_err(|_| invalid_data("head is not UTF-8"))?;
+        let mut lines = head.split("\r\n");
+        let start_line = lines.next().unwrap_or_default().to_string();
+        let headers = lines
+            .map(|line| {
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| invalid_data("malformed header"))?;
+                Ok((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Some(Head {
+            start_line,
+            headers,
+        }))
+    }
+
+    /// Read a body of up to `max_size` bytes, a longer one fails before it
+    /// is buffered completely
+    pub(crate) async fn read_body(
+        &mut self,
+        framing: Framing,
+        max_size: usize,
+    ) -> io::Result<Vec<u8>> {
+        match framing {
+            Framing::Empty => Ok(Vec::new()),
+            Framing::Length(length) if length > max_size => Err(too_large("message body")),
+            Framing::Length(length) => self.read_exact(length).await,
+            Framing::Chunked => {
+                let mut body = Vec::new();
+                loop {
+                    let line = self.read_line().await?;
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    let size = usize::from_str_radix(size, 16)
+                        .map_err(|_| invalid_data("invalid chunk size"))?;
+                    if size == 0 {
+                        // Skip the (usually empty) trailer
+                        while !self.read_line().await?.is_empty() {}
+                        return Ok(body);
+                    }
+                    if size > max_size - body.len() {
+                        return Err(too_large("message body"));
+                    }
+                    body.extend(self.read_exact(size).await?);
+                    if !self.read_line().await?.is_empty() {
+                        return Err(invalid_data("chunk not terminated by CRLF"));
+                    }
+                }
+            }
+            Framing::UntilClose => {
+                // Part of the body may have arrived with the head already
+                loop {
+                    if self.buffer.len() > max_size {
+                        return Err(too_large("message body"));
+                    }
+                    if self.fill().await? == 0 {
+                        break;
+                    }
+                }
+                Ok(std::mem::take(&mut self.buffer))
+            }
+        }
+    }
+
+    async fn read_exact(&mut self, length: usize) -> io::Result<Vec<u8>> {
+        while self.buffer.len() < length {
+            if self.fill().await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        Ok(self.buffer.drain(..length).collect())
+    }
+
+    async fn read_line(&mut self) -> io::Result<String> {
+        loop {
+            if let Some(pos) = self.buffer.windows(2).position(|w| w == b"\r\n") {
+                let line: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+                return String::from_utf8(line[..pos].to_vec())
+                    .map_err(|_| invalid_data("line is not UTF-8"));
+            }
+            if self.buffer.len() > MAX_HEAD_SIZE {
+                return Err(invalid_data("line too long"));
+            }
+            if self.fill().await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+    }
+}
+
+/// Serialize a message: start line, headers, empty line and the body.
+/// Fails with `InvalidInput` on a line break in the start line or a header,
+/// which would smuggle in headers of its own.
+pub(crate) fn encode(
+    start_line: &str,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> io::Result<Vec<u8>> {
+    let has_line_break = |s: &str| s.contains(['\r', '\n']);
+    if has_line_break(start_line) {
+        return Err(invalid_input("line break in the start line"));
+    }
+    let mut message = format!("{start_line}\r\n");
+    for (name, value) in headers {
+        if name.is_empty() || name.contains(':') || has_line_break(name) {
+            return Err(invalid_input("invalid header name"));
+        }
+        if has_line_break(value) {
+            return Err(invalid_input("line break in a header value"));
+        }
+        message.push_str(&format!("{name}: {value}\r\n"));
+    }
+    message.push_str("\r\n");
+    let mut message = message.into_bytes();
+    message.extend_from_slice(body);
+    Ok(message)
+}
@@ -0,0 +1,321 @@
+use super::{
+    Request, Response,
+    proto::{self, Connection, Framing, Head},
+};
+use crate::net::{TcpListener, TcpStream};
+use std::{
+    cell::{Cell, RefCell},
+    future::{Future, poll_fn},
+    io,
+    net::{Shutdown, SocketAddr, ToSocketAddrs},
+    pin::pin,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+/// Handles the requests of a `Server`, implemented for every
+/// `Fn(Request) -> impl Future<Output = Response>`, e.g. an `async fn`.
+pub trait Service {
+    fn call(&self, request: Request) -> impl Future<Output = Response>;
+}
+
+impl<F, Fut> Service for F
+where
+    F: Fn(Request) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    fn call(&self, request: Request) -> impl Future<Output = Response> {
+        self(request)
+    }
+}
+
+/// HTTP/1.1 server, every connection is handled by its own task, so `serve`
+/// has to run on an `Executor`.
+///
+/// Requests on a connection are answered one after the other, even if the
+/// client pipelines them. A request that can't be framed unambiguously
+/// (e.g. `Content-Length` and `Transfer-Encoding`) is answered with `400`
+/// and the connection is closed, so the following bytes are never taken as
+/// a new request.
+///
+/// A request with a head bigger than `max_head_size` is answered with `431`,
+/// one with a body bigger than `max_body_size` with `413`, before the body
+/// is read if its length is announced.
+pub struct Server {
+    listener: TcpListener,
+    limits: Limits,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    head: usize,
+    body: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            head: proto::MAX_HEAD_SIZE,
+            body: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// Shared between the accept loop and the connection tasks
+#[derive(Default)]
+struct State {
+    shutting_down: Cell<bool>,
+    connections: Cell<usize>,
+    // Tasks waiting for the shutdown or for the last connection to finish
+    waiters: RefCell<Vec<Waker>>,
+}
+
+impl State {
+    fn notify(&self) {
+        self.waiters.borrow_mut().drain(..).for_each(Waker::wake);
+    }
+
+    async fn wait_until(&self, condition: impl Fn(&Self) -> bool) {
+        poll_fn(|cx| {
+            if condition(self) {
+                return Poll::Ready(());
+            }
+            let mut waiters = self.waiters.borrow_mut();
+            if !waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+                waiters.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+/// Poll both futures, return the output of the one that finishes first
+async fn first<T>(a: impl Future<Output = T>, b: impl Future<Output = T>) -> T {
+    let (mut a, mut b) = (pin!(a), pin!(b));
+    poll_fn(|cx| match a.as_mut().poll(cx) {
+        Poll::Ready(output) => Poll::Ready(output),
+        Poll::Pending => b.as_mut().poll(cx),
+    })
+    .await
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Server> {
+        Ok(Self::from_listener(TcpListener::bind(addr)?))
+    }
+
+    pub fn from_listener(listener: TcpListener) -> Server {
+        Server {
+            listener,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Largest request line plus headers in bytes, 64 KiB by default
+    pub fn max_head_size(mut self, max: usize) -> Self {
+        self.limits.head = max;
+        self
+    }
+
+    /// Largest request body in bytes, 2 MiB by default
+    pub fn max_body_size(mut self, max: usize) -> Self {
+        self.limits.body = max;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept and serve connections forever
+    pub async fn serve(self, service: impl Service + 'static) -> io::Result<()> {
+        self.serve_with_shutdown(service, std::future::pending())
+            .await
+    }
+
+    /// Accept and serve connections until `signal` completes. Then no new
+    /// connections are accepted and idle connections are closed. Requests
+    /// in flight, including the ones that were only received in part, are
+    /// answered and the future completes once every connection is closed.
+    pub async fn serve_with_shutdown(
+        self,
+        service: impl Service + 'static,
+        signal: impl Future<Output = ()>,
+    ) -> io::Result<()> {
+        let limits = self.limits;
+        let service = Rc::new(service);
+        let state = Rc::new(State::default());
+        let mut signal = pin!(signal);
+        loop {
+            let accepted = first(async { Some(self.listener.accept().await) }, async {
+                signal.as_mut().await;
+                None
+            })
+            .await;
+            let stream = match accepted {
+                Some(Ok((stream, _))) => stream,
+                Some(Err(e)) => {
+                    eprintln!("ERROR: accept failed: {e}");
+                    continue;
+                }
+                None => break,
+            };
+            state.connections.set(state.connections.get() + 1);
+            let (service, state) = (service.clone(), state.clone());
+            crate::spawn(async move {
+                if let Err(e) = serve_connection(stream, &*service, &state, limits).await {
+                    eprintln!("ERROR: connection failed: {e}");
+                }
+                state.connections.set(state.connections.get() - 1);
+                state.notify();
+            });
+        }
+        state.shutting_down.set(true);
+        state.notify();
+        state.wait_until(|state| state.connections.get() == 0).await;
+        Ok(())
+    }
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    service: &impl Service,
+    state: &State,
+    limits: Limits,
+) -> io::Result<()> {
+    let mut connection = Connection::new(stream);
+    loop {
+        // An idle connection is closed right away on shutdown, but once the
+        // first byte of a request arrived, the request is read and answered
+        if connection.buffered() == 0 {
+            let received = first(async { Some(connection.fill().await) }, async {
+                state.wait_until(|state| state.shutting_down.get()).await;
+                None
+            })
+            .await;
+            match received {
+                Some(Ok(0)) | None => break,
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(e),
+            }
+        }
+        let head = match connection.read_head(limits.head).await {
+            Ok(Some(head)) => head,
+            // The client closed the connection
+            Ok(None) => break,
+            Err(e) if proto::is_too_large(&e) => {
+                let response = Response::new(431, e.to_string());
+                write_response(&connection, response, false, false).await?;
+                break;
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                let response = Response::new(400, e.to_string());
+                write_response(&connection, response, false, false).await?;
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let (request, keep_alive) = match read_request(&mut connection, head, limits.body).await? {
+            Ok(request) => request,
+            Err(rejection) => {
+                write_response(&connection, rejection, false, false).await?;
+                break;
+            }
+        };
+        let is_head = request.method == "HEAD";
+        let response = service.call(request).await;
+        let keep_alive = keep_alive && !state.shutting_down.get();
+        write_response(&connection, response, keep_alive, is_head).await?;
+        if !keep_alive {
+            break;
+        }
+    }
+    connection.stream.shutdown(Shutdown::Write).or_else(|e| {
+        // The client might be gone already
+        if e.kind() == io::ErrorKind::NotConnected {
+            Ok(())
+        } else {
+            Err(e)
+        }
+    })
+}
+
+/// Parse the request line and read the body. Returns the response for
+/// requests that are rejected, e.g. because they can't be framed safely or
+/// are too large, the connection must be closed then.
+async fn read_request(
+    connection: &mut Connection,
+    head: Head,
+    max_body_size: usize,
+) -> io::Result<Result<(Request, bool), Response>> {
+    let malformed = || Err(Response::new(400, "malformed request"));
+    let too_large = || Err(Response::new(413, "request body too large"));
+    let mut parts = head.start_line.split(' ');
+    let (Some(method), Some(path), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Ok(malformed());
+    };
+    if !matches!(version, "HTTP/1.1" | "HTTP/1.0") || method.is_empty() || path.is_empty() {
+        return Ok(malformed());
+    }
+    let has_length = head.header("Content-Length").is_some();
+    let framing = match head.framing() {
+        Ok(Some(Framing::Chunked)) if has_length => return Ok(malformed()),
+        Ok(Some(Framing::Length(length))) if length > max_body_size => return Ok(too_large()),
+        Ok(framing) => framing.unwrap_or(Framing::Empty),
+        Err(_) => return Ok(malformed()),
+    };
+    if framing != Framing::Empty
+        && head
+            .header("Expect")
+            .is_some_and(|expect| expect.eq_ignore_ascii_case("100-continue"))
+    {
+        connection
+            .stream
+            .write_all(b"HTTP/1.1 100 Continue\r\n\r\n")
+            .await?;
+    }
+    let body = match connection.read_body(framing, max_body_size).await {
+        Ok(body) => body,
+        Err(e) if proto::is_too_large(&e) => return Ok(too_large()),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => return Ok(malformed()),
+        Err(e) => return Err(e),
+    };
+    let keep_alive = head.keep_alive(version);
+    let request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers: head.headers,
+        body,
+    };
+    Ok(Ok((request, keep_alive)))
+}
+
+async fn write_response(
+    connection: &Connection,
+    mut response: Response,
+    keep_alive: bool,
+    is_head: bool,
+) -> io::Result<()> {
+    if response.header("Content-Length").is_none() {
+        let length = response.body.len().to_string();
+        response
+            .headers
+            .push(("Content-Length".to_string(), length));
+    }
+    if !keep_alive {
+        response
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("Connection"));
+        response
+            .headers
+            .push(("Connection".to_string(), "close".to_string()));
+    }
+    let start_line = format!("HTTP/1.1 {} {}", response.status, response.reason);
+    let body = if is_head { &[][..] } else { &response.body };
+    let message = proto::encode(&start_line, &response.headers, body)?;
+    connection.stream.write_all(&message).await
+}
@@ -0,0 +1,397 @@
+//! Asynchronous IO on top of the reactor: the `AsyncRead`, `AsyncWrite` and
+//! `AsyncBufRead` traits, buffering and helpers to move bytes around.
+//!
+//! ```text
+//! let (reader, mut writer) = io::split(stream);
+//! let mut lines = BufReader::new(reader).lines();
+//! while let Some(line) = lines.next().await {
+//!     writer.write_all(line?.to_uppercase().as_bytes()).await?;
+//! }
+//! ```
+mod async_fd;
+mod buf_reader;
+mod buf_writer;
+mod copy;
+mod lines;
+mod split;
+
+pub use async_fd::{AsyncFd, AsyncFdReadyGuard, TryIoError};
+pub use buf_reader::BufReader;
+pub use buf_writer::BufWriter;
+pub use copy::{copy, copy_bidirectional};
+pub use lines::Lines;
+pub use split::{ReadHalf, WriteHalf, split};
+
+use std::{
+    future::{Future, poll_fn},
+    io,
+    ops::DerefMut,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Source of bytes, the asynchronous version of `std::io::Read`
+pub trait AsyncRead {
+    /// Read into `buf`, `Ready(Ok(0))` means end of file (or an empty `buf`).
+    /// Pending until there is something to read, the task is woken then.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>>;
+}
+
+/// Destination of bytes, the asynchronous version of `std::io::Write`
+pub trait AsyncWrite {
+    /// Write some of `buf`, returns how many bytes were written
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>>;
+
+    /// Wait until everything buffered was written to the destination
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+
+    /// Flush and shut the writing side down, e.g. to send EOF to a peer
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
+}
+
+/// A reader with an internal buffer, the asynchronous version of
+/// `std::io::BufRead`
+pub trait AsyncBufRead: AsyncRead {
+    /// The buffered bytes, the buffer is refilled first if it is empty. An
+    /// empty slice means end of file.
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>>;
+
+    /// Mark `amt` bytes of the buffer as read
+    fn consume(self: Pin<&mut Self>, amt: usize);
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> AsyncRead for &mut R {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+impl<R: AsyncRead + Unpin + ?Sized> AsyncRead for Box<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_read(cx, buf)
+    }
+}
+
+impl<P> AsyncRead for Pin<P>
+where
+    P: DerefMut<Target: AsyncRead>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.as_deref_mut().poll_read(cx, buf)
+    }
+}
+
+impl AsyncRead for &[u8] {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(io::Read::read(&mut *self, buf))
+    }
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> AsyncWrite for &mut W {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
+
+impl<W: AsyncWrite + Unpin + ?Sized> AsyncWrite for Box<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut **self).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
+
+impl<P> AsyncWrite for Pin<P>
+where
+    P: DerefMut<Target: AsyncWrite>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.as_deref_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.as_deref_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.as_deref_mut().poll_close(cx)
+    }
+}
+
+impl AsyncWrite for Vec<u8> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R: AsyncBufRead + Unpin + ?Sized> AsyncBufRead for &mut R {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut **self.get_mut()).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut **self).consume(amt)
+    }
+}
+
+impl<R: AsyncBufRead + Unpin + ?Sized> AsyncBufRead for Box<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut **self.get_mut()).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut **self).consume(amt)
+    }
+}
+
+impl AsyncBufRead for &[u8] {
+    fn poll_fill_buf(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(Ok(*self.get_mut()))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        *self = &self[amt..];
+    }
+}
+
+/// Helpers for every `AsyncRead`
+pub trait AsyncReadExt: AsyncRead {
+    /// Read into `buf`, returns how many bytes were read, `0` at end of file
+    fn read<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        poll_fn(move |cx| Pin::new(&mut *self).poll_read(cx, buf))
+    }
+
+    /// Fill all of `buf`, fails with `UnexpectedEof` if the reader ends first
+    fn read_exact<'a>(&'a mut self, buf: &'a mut [u8]) -> impl Future<Output = io::Result<()>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let mut filled = 0;
+            while filled < buf.len() {
+                match self.read(&mut buf[filled..]).await? {
+                    0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                    n => filled += n,
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Append everything until end of file to `buf`, returns how many bytes
+    /// were read
+    fn read_to_end<'a>(
+        &'a mut self,
+        buf: &'a mut Vec<u8>,
+    ) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let start = buf.len();
+            let mut filled = Filled { len: start, buf };
+            loop {
+                if filled.len == filled.buf.capacity() {
+                    // Grows the capacity exponentially
+                    filled.buf.reserve(32);
+                }
+                let (len, capacity) = (filled.len, filled.buf.capacity());
+                filled.buf.resize(capacity, 0);
+                match self.read(&mut filled.buf[len..]).await? {
+                    0 => return Ok(len - start),
+                    n => filled.len += n,
+                }
+            }
+        }
+    }
+}
+
+/// Cuts the zeroes that weren't read into off the buffer of `read_to_end`,
+/// also when it fails or is dropped while waiting
+struct Filled<'a> {
+    buf: &'a mut Vec<u8>,
+    len: usize,
+}
+
+impl Drop for Filled<'_> {
+    fn drop(&mut self) {
+        self.buf.truncate(self.len);
+    }
+}
+
+impl<R: AsyncRead + ?Sized> AsyncReadExt for R {}
+
+/// Helpers for every `AsyncWrite`
+pub trait AsyncWriteExt: AsyncWrite {
+    /// Write some of `buf`, returns how many bytes were written
+    fn write<'a>(&'a mut self, buf: &'a [u8]) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        poll_fn(move |cx| Pin::new(&mut *self).poll_write(cx, buf))
+    }
+
+    /// Write all of `buf`, fails with `WriteZero` if the writer stops taking
+    /// bytes
+    fn write_all<'a>(&'a mut self, mut buf: &'a [u8]) -> impl Future<Output = io::Result<()>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            while !buf.is_empty() {
+                match self.write(buf).await? {
+                    0 => return Err(io::ErrorKind::WriteZero.into()),
+                    n => buf = &buf[n..],
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn flush(&mut self) -> impl Future<Output = io::Result<()>> + '_
+    where
+        Self: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_flush(cx))
+    }
+
+    fn close(&mut self) -> impl Future<Output = io::Result<()>> + '_
+    where
+        Self: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_close(cx))
+    }
+}
+
+impl<W: AsyncWrite + ?Sized> AsyncWriteExt for W {}
+
+/// Helpers for every `AsyncBufRead`
+pub trait AsyncBufReadExt: AsyncBufRead {
+    /// Append everything up to and including `byte` to `buf`, or until end
+    /// of file. Returns how many bytes were read.
+    fn read_until<'a>(
+        &'a mut self,
+        byte: u8,
+        buf: &'a mut Vec<u8>,
+    ) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        let mut read = 0;
+        poll_fn(move |cx| {
+            loop {
+                let available = match Pin::new(&mut *self).poll_fill_buf(cx) {
+                    Poll::Ready(Ok(available)) => available,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                };
+                let (done, used) = match available.iter().position(|b| *b == byte) {
+                    Some(i) => (true, i + 1),
+                    None => (available.is_empty(), available.len()),
+                };
+                buf.extend_from_slice(&available[..used]);
+                Pin::new(&mut *self).consume(used);
+                read += used;
+                if done {
+                    return Poll::Ready(Ok(read));
+                }
+            }
+        })
+    }
+
+    /// Append the next line including its `\n` to `buf`, returns how many
+    /// bytes were read, `0` at end of file. Fails with `InvalidData` if the
+    /// line is not UTF-8.
+    fn read_line<'a>(
+        &'a mut self,
+        buf: &'a mut String,
+    ) -> impl Future<Output = io::Result<usize>> + 'a
+    where
+        Self: Unpin,
+    {
+        async move {
+            let mut line = Vec::new();
+            let n = self.read_until(b'\n', &mut line).await?;
+            let line = String::from_utf8(line)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "line is not UTF-8"))?;
+            buf.push_str(&line);
+            Ok(n)
+        }
+    }
+
+    /// A `Stream` of the lines without their `\n` or `\r\n`
+    fn lines(self) -> Lines<Self>
+    where
+        Self: Sized,
+    {
+        Lines::new(self)
+    }
+}
+
+impl<R: AsyncBufRead + ?Sized> AsyncBufReadExt for R {}
@@ -0,0 +1,161 @@
+use crate::reactor::{READABLE, ReadyEvent, WRITABLE, reactor};
+use std::{
+    fmt, io,
+    os::fd::{AsRawFd, RawFd},
+    task::{Context, Poll},
+};
+
+/// Wraps any file descriptor (socket, eventfd, device fd, ...) that is in
+/// non-blocking mode and registers it with the reactor, so tasks can wait
+/// until it is readable or writable instead of busy looping on `WouldBlock`.
+pub struct AsyncFd<T: AsRawFd> {
+    inner: Option<T>,
+    token: usize,
+}
+
+/// Returned by `AsyncFd::readable`/`AsyncFd::writable`. As long as the
+/// readiness is not cleared, the fd is reported as ready again.
+pub struct AsyncFdReadyGuard<'a, T: AsRawFd> {
+    fd: &'a AsyncFd<T>,
+    event: Option<ReadyEvent>,
+}
+
+/// The IO passed to `AsyncFdReadyGuard::try_io` would have blocked,
+/// the readiness was cleared and the task has to wait again.
+#[derive(Debug)]
+pub struct TryIoError(());
+
+impl<T: AsRawFd> AsyncFd<T> {
+    pub fn new(inner: T) -> io::Result<Self> {
+        let token = reactor().register(&inner)?;
+        Ok(AsyncFd {
+            inner: Some(inner),
+            token,
+        })
+    }
+
+    pub fn get_ref(&self) -> &T {
+        self.inner.as_ref().unwrap()
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Deregister the fd from the reactor and give it back
+    pub fn into_inner(mut self) -> T {
+        self.deregister();
+        self.inner.take().unwrap()
+    }
+
+    /// Wait until the fd is readable. Any number of tasks can wait at the
+    /// same time, all of them are woken.
+    pub async fn readable(&self) -> io::Result<AsyncFdReadyGuard<'_, T>> {
+        std::future::poll_fn(|cx| self.poll_read_ready(cx)).await
+    }
+
+    /// Wait until the fd is writable
+    pub async fn writable(&self) -> io::Result<AsyncFdReadyGuard<'_, T>> {
+        std::future::poll_fn(|cx| self.poll_write_ready(cx)).await
+    }
+
+    pub fn poll_read_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        self.poll_ready(READABLE, cx)
+    }
+
+    pub fn poll_write_ready(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        self.poll_ready(WRITABLE, cx)
+    }
+
+    fn poll_ready(
+        &self,
+        interest: u32,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<AsyncFdReadyGuard<'_, T>>> {
+        reactor()
+            .poll_ready(self.token, interest, cx)
+            .map_ok(|event| AsyncFdReadyGuard {
+                fd: self,
+                event: Some(event),
+            })
+    }
+
+    fn deregister(&self) {
+        if let Some(inner) = &self.inner
+            && let Err(e) = reactor().deregister(inner, self.token)
+        {
+            eprintln!("ERROR: {e:?}");
+        }
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for AsyncFd<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.get_ref().as_raw_fd()
+    }
+}
+
+impl<T: AsRawFd + fmt::Debug> fmt::Debug for AsyncFd<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncFd")
+            .field("inner", &self.inner)
+            .field("token", &self.token)
+            .finish()
+    }
+}
+
+impl<T: AsRawFd> Drop for AsyncFd<T> {
+    fn drop(&mut self) {
+        self.deregister();
+    }
+}
+
+impl<'a, T: AsRawFd> AsyncFdReadyGuard<'a, T> {
+    pub fn get_ref(&self) -> &'a AsyncFd<T> {
+        self.fd
+    }
+
+    /// Forget the readiness, call this after the IO returned `WouldBlock`.
+    /// The next `readable`/`writable` waits for a new event from the OS.
+    pub fn clear_ready(&mut self) {
+        if let Some(event) = self.event.take() {
+            reactor().clear_readiness(self.fd.token, event);
+        }
+    }
+
+    /// Keep the readiness, the next `readable`/`writable` returns immediately
+    pub fn retain_ready(&mut self) {
+        self.event = None;
+    }
+
+    /// Run the IO in `f` and clear the readiness if it returns `WouldBlock`.
+    /// This is the read-until-`WouldBlock` loop of an edge-triggered event queue:
+    ///
+    /// ```text
+    /// loop {
+    ///     let mut guard = fd.readable().await?;
+    ///     match guard.try_io(|fd| fd.get_ref().read(&mut buf)) {
+    ///         Ok(result) => return result,
+    ///         Err(_would_block) => continue,
+    ///     }
+    /// }
+    /// ```
+    pub fn try_io<R>(
+        &mut self,
+        f: impl FnOnce(&'a AsyncFd<T>) -> io::Result<R>,
+    ) -> Result<io::Result<R>, TryIoError> {
+        match f(self.fd) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                self.clear_ready();
+                Err(TryIoError(()))
+            }
+            result => Ok(result),
+        }
+    }
+}
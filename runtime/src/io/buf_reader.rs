@@ -0,0 +1,92 @@
+use super::{AsyncBufRead, AsyncRead};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Reads from `inner` in big chunks, so many small reads (e.g. line by line)
+/// don't each end up in a system call
+pub struct BufReader<R> {
+    inner: Pin<Box<R>>,
+    buffer: Box<[u8]>,
+    // The unread bytes are `buffer[pos..filled]`
+    pos: usize,
+    filled: usize,
+}
+
+// Only the boxed reader is pinned
+impl<R> Unpin for BufReader<R> {}
+
+impl<R> BufReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: R) -> Self {
+        BufReader {
+            inner: Box::pin(inner),
+            buffer: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// The unread buffered bytes
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer[self.pos..self.filled]
+    }
+}
+
+impl<R: Unpin> BufReader<R> {
+    /// Reading from the inner reader directly skips the buffered bytes
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// The buffered bytes are lost
+    pub fn into_inner(self) -> R {
+        *Pin::into_inner(self.inner)
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for BufReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // Nothing buffered and a big read: the buffer would only add a copy
+        if this.pos == this.filled && buf.len() >= this.buffer.len() {
+            return this.inner.as_mut().poll_read(cx, buf);
+        }
+        let available = ready!(Pin::new(&mut *this).poll_fill_buf(cx))?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        Pin::new(this).consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<R: AsyncRead> AsyncBufRead for BufReader<R> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos == this.filled {
+            this.filled = ready!(this.inner.as_mut().poll_read(cx, &mut this.buffer))?;
+            this.pos = 0;
+        }
+        Poll::Ready(Ok(&this.buffer[this.pos..this.filled]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        this.pos = (this.pos + amt).min(this.filled);
+    }
+}
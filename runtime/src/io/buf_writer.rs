@@ -0,0 +1,106 @@
+use super::AsyncWrite;
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Collects small writes and passes them to `inner` in big chunks. Call
+/// `flush` (or `close`) when done, buffered bytes are lost on drop.
+pub struct BufWriter<W> {
+    inner: Pin<Box<W>>,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+// Only the boxed writer is pinned
+impl<W> Unpin for BufWriter<W> {}
+
+impl<W> BufWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY, inner)
+    }
+
+    pub fn with_capacity(capacity: usize, inner: W) -> Self {
+        BufWriter {
+            inner: Box::pin(inner),
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The bytes not written to the inner writer yet
+    pub fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl<W: Unpin> BufWriter<W> {
+    /// Writing to the inner writer directly skips ahead of the buffered bytes
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// The buffered bytes are lost, flush first
+    pub fn into_inner(self) -> W {
+        *Pin::into_inner(self.inner)
+    }
+}
+
+impl<W: AsyncWrite> BufWriter<W> {
+    /// Pass the whole buffer on to the inner writer
+    fn poll_write_buffer(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut written = 0;
+        let result = loop {
+            if written == self.buffer.len() {
+                break Poll::Ready(Ok(()));
+            }
+            match self.inner.as_mut().poll_write(cx, &self.buffer[written..]) {
+                Poll::Ready(Ok(0)) => break Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(n)) => written += n,
+                Poll::Ready(Err(e)) => break Poll::Ready(Err(e)),
+                Poll::Pending => break Poll::Pending,
+            }
+        };
+        // Also on errors, the written part must not be written again
+        self.buffer.drain(..written);
+        result
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for BufWriter<W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.buffer.len() + buf.len() > this.capacity {
+            ready!(this.poll_write_buffer(cx))?;
+        }
+        // Too big to ever fit: the buffer would only add a copy
+        if buf.len() >= this.capacity {
+            return this.inner.as_mut().poll_write(cx, buf);
+        }
+        this.buffer.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buffer(cx))?;
+        this.inner.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buffer(cx))?;
+        this.inner.as_mut().poll_close(cx)
+    }
+}
@@ -0,0 +1,125 @@
+use super::{AsyncRead, AsyncWrite};
+use std::{
+    future::poll_fn,
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Copy everything from `reader` to `writer` until the reader ends, then
+/// flush the writer. Returns how many bytes were copied.
+pub async fn copy<R, W>(reader: &mut R, writer: &mut W) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let mut transfer = Transfer::new(false);
+    poll_fn(|cx| transfer.poll_copy(cx, Pin::new(&mut *reader), Pin::new(&mut *writer))).await
+}
+
+/// Copy in both directions at the same time, e.g. to proxy a connection.
+/// When one side ends, the other side's writing half is closed. Returns the
+/// bytes copied from `a` to `b` and from `b` to `a` once both ended.
+pub async fn copy_bidirectional<A, B>(a: &mut A, b: &mut B) -> io::Result<(u64, u64)>
+where
+    A: AsyncRead + AsyncWrite + Unpin + ?Sized,
+    B: AsyncRead + AsyncWrite + Unpin + ?Sized,
+{
+    let mut a_to_b = Transfer::new(true);
+    let mut b_to_a = Transfer::new(true);
+    let mut a_to_b_done = None;
+    let mut b_to_a_done = None;
+    poll_fn(|cx| {
+        if a_to_b_done.is_none()
+            && let Poll::Ready(n) = a_to_b.poll_copy(cx, Pin::new(&mut *a), Pin::new(&mut *b))
+        {
+            a_to_b_done = Some(n?);
+        }
+        if b_to_a_done.is_none()
+            && let Poll::Ready(n) = b_to_a.poll_copy(cx, Pin::new(&mut *b), Pin::new(&mut *a))
+        {
+            b_to_a_done = Some(n?);
+        }
+        match (a_to_b_done, b_to_a_done) {
+            (Some(a_to_b), Some(b_to_a)) => Poll::Ready(Ok((a_to_b, b_to_a))),
+            _ => Poll::Pending,
+        }
+    })
+    .await
+}
+
+/// The state of copying one direction, kept between polls
+struct Transfer {
+    buffer: Box<[u8]>,
+    // The bytes read but not written yet are `buffer[pos..filled]`
+    pos: usize,
+    filled: usize,
+    reader_done: bool,
+    // Close the writer once the reader ended, instead of only flushing it
+    close: bool,
+    copied: u64,
+}
+
+impl Transfer {
+    fn new(close: bool) -> Self {
+        Transfer {
+            buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            reader_done: false,
+            close,
+            copied: 0,
+        }
+    }
+
+    /// Ready once the reader ended and everything was written and flushed
+    /// (or the writer closed)
+    fn poll_copy<R, W>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut reader: Pin<&mut R>,
+        mut writer: Pin<&mut W>,
+    ) -> Poll<io::Result<u64>>
+    where
+        R: AsyncRead + ?Sized,
+        W: AsyncWrite + ?Sized,
+    {
+        loop {
+            if self.pos == self.filled && !self.reader_done {
+                match reader.as_mut().poll_read(cx, &mut self.buffer)? {
+                    Poll::Ready(0) => self.reader_done = true,
+                    Poll::Ready(n) => {
+                        self.pos = 0;
+                        self.filled = n;
+                    }
+                    Poll::Pending => {
+                        // Nothing to read right now, make sure what was
+                        // written so far doesn't sit in a buffer
+                        ready!(writer.as_mut().poll_flush(cx))?;
+                        return Poll::Pending;
+                    }
+                }
+            }
+            while self.pos < self.filled {
+                let buf = &self.buffer[self.pos..self.filled];
+                match ready!(writer.as_mut().poll_write(cx, buf))? {
+                    0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                    n => {
+                        self.pos += n;
+                        self.copied += n as u64;
+                    }
+                }
+            }
+            if self.reader_done {
+                if self.close {
+                    ready!(writer.as_mut().poll_close(cx))?;
+                } else {
+                    ready!(writer.as_mut().poll_flush(cx))?;
+                }
+                return Poll::Ready(Ok(self.copied));
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::AsyncBufRead;
+use crate::stream::Stream;
+use std::{
+    io, mem,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+/// The lines of a reader as a `Stream`, without their `\n` or `\r\n`.
+/// Created by `AsyncBufReadExt::lines`.
+pub struct Lines<R> {
+    reader: Pin<Box<R>>,
+    // The start of a line that was read before the reader was pending
+    line: Vec<u8>,
+}
+
+// Only the boxed reader is pinned
+impl<R> Unpin for Lines<R> {}
+
+impl<R> Lines<R> {
+    pub(super) fn new(reader: R) -> Self {
+        Lines {
+            reader: Box::pin(reader),
+            line: Vec::new(),
+        }
+    }
+}
+
+impl<R: AsyncBufRead> Stream for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<io::Result<String>>> {
+        let this = self.get_mut();
+        loop {
+            let available = ready!(this.reader.as_mut().poll_fill_buf(cx))?;
+            if available.is_empty() {
+                // A last line without `\n` is still a line
+                if this.line.is_empty() {
+                    return Poll::Ready(None);
+                }
+                break;
+            }
+            match available.iter().position(|b| *b == b'\n') {
+                Some(i) => {
+                    this.line.extend_from_slice(&available[..i]);
+                    this.reader.as_mut().consume(i + 1);
+                    break;
+                }
+                None => {
+                    let n = available.len();
+                    this.line.extend_from_slice(available);
+                    this.reader.as_mut().consume(n);
+                }
+            }
+        }
+        let mut line = mem::take(&mut this.line);
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        let line = String::from_utf8(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "line is not UTF-8"));
+        Poll::Ready(Some(line))
+    }
+}
@@ -0,0 +1,72 @@
+use super::{AsyncRead, AsyncWrite};
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// Split `stream` into a reading and a writing half that can be used by
+/// different tasks. The halves share the stream behind a lock that is only
+/// held while polling. The stream has to keep separate wakers for reading
+/// and writing (`TcpStream` does), otherwise the halves replace each
+/// other's waker.
+pub fn split<T: AsyncRead + AsyncWrite>(stream: T) -> (ReadHalf<T>, WriteHalf<T>) {
+    let inner = Arc::new(Mutex::new(Box::pin(stream)));
+    (
+        ReadHalf {
+            inner: inner.clone(),
+        },
+        WriteHalf { inner },
+    )
+}
+
+pub struct ReadHalf<T> {
+    inner: Arc<Mutex<Pin<Box<T>>>>,
+}
+
+pub struct WriteHalf<T> {
+    inner: Arc<Mutex<Pin<Box<T>>>>,
+}
+
+impl<T: Unpin> ReadHalf<T> {
+    /// Put the stream back together, panics if the halves are from
+    /// different `split` calls
+    pub fn unsplit(self, write: WriteHalf<T>) -> T {
+        assert!(
+            Arc::ptr_eq(&self.inner, &write.inner),
+            "unsplit with halves of different streams"
+        );
+        drop(write);
+        let inner = Arc::into_inner(self.inner).unwrap();
+        *Pin::into_inner(inner.into_inner().unwrap())
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for ReadHalf<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.inner.lock().unwrap().as_mut().poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for WriteHalf<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.inner.lock().unwrap().as_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.lock().unwrap().as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.inner.lock().unwrap().as_mut().poll_close(cx)
+    }
+}
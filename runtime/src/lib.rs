@@ -1,4 +1,24 @@
+#[macro_use]
+mod macros;
+
+pub mod blocking;
+pub mod codec;
+mod coop;
+mod ffi;
+pub mod fs;
+pub mod future;
+pub mod http;
+pub mod io;
+pub mod net;
+mod reactor;
 mod runtime;
+pub mod sink;
+pub mod stream;
+pub mod sync;
+pub mod task;
+pub mod time;
 
+pub use blocking::spawn_blocking;
 pub use runtime::Executor;
 pub use runtime::MyWaker;
+pub use runtime::spawn;
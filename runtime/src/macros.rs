@@ -0,0 +1,282 @@
+/// Poll several futures concurrently in the current task and wait for all of
+/// them, the outputs are returned as a tuple.
+///
+/// ```text
+/// let (a, b) = runtime::join!(fetch("a"), fetch("b"));
+/// ```
+#[macro_export]
+macro_rules! join {
+    (@{ ( $($count:tt)* ) $( ( $($skip:tt)* ) $e:expr, )* }) => {{
+        let mut futures = ( $( ::std::pin::pin!($crate::future::MaybeDone::new($e)), )* );
+        ::std::future::poll_fn(|cx| {
+            let mut done = true;
+            $({
+                let ( $($skip,)* fut, .. ) = &mut futures;
+                done &= fut.as_mut().poll_done(cx).is_ready();
+            })*
+            if !done {
+                return ::std::task::Poll::Pending;
+            }
+            ::std::task::Poll::Ready(( $({
+                let ( $($skip,)* fut, .. ) = &mut futures;
+                fut.as_mut().take_output().unwrap()
+            }, )* ))
+        })
+        .await
+    }};
+    // Give every future the list of `_` that skips the ones before it in the tuple
+    (@{ ( $($count:tt)* ) $($done:tt)* } $e:expr, $($rest:tt)*) => {
+        $crate::join!(@{ ( $($count)* _ ) $($done)* ( $($count)* ) $e, } $($rest)*)
+    };
+    ( $($e:expr),+ $(,)? ) => {
+        $crate::join!(@{ () } $($e,)+)
+    };
+}
+
+/// Like `join!` for futures that return a `Result`. Fails with the first
+/// error, the other futures are dropped then.
+///
+/// ```text
+/// let (a, b) = runtime::try_join!(fetch("a"), fetch("b"))?;
+/// ```
+#[macro_export]
+macro_rules! try_join {
+    (@{ ( $($count:tt)* ) $( ( $($skip:tt)* ) $e:expr, )* }) => {{
+        let mut futures = ( $( ::std::pin::pin!($crate::future::MaybeDone::new($e)), )* );
+        ::std::future::poll_fn(|cx| {
+            let mut done = true;
+            $({
+                let ( $($skip,)* fut, .. ) = &mut futures;
+                if fut.as_mut().poll_done(cx).is_ready() {
+                    if let Some(e) = fut.as_mut().take_err() {
+                        return ::std::task::Poll::Ready(Err(e));
+                    }
+                } else {
+                    done = false;
+                }
+            })*
+            if !done {
+                return ::std::task::Poll::Pending;
+            }
+            ::std::task::Poll::Ready(Ok(( $({
+                let ( $($skip,)* fut, .. ) = &mut futures;
+                fut.as_mut().take_output().unwrap().ok().unwrap()
+            }, )* )))
+        })
+        .await
+    }};
+    (@{ ( $($count:tt)* ) $($done:tt)* } $e:expr, $($rest:tt)*) => {
+        $crate::try_join!(@{ ( $($count)* _ ) $($done)* ( $($count)* ) $e, } $($rest)*)
+    };
+    ( $($e:expr),+ $(,)? ) => {
+        $crate::try_join!(@{ () } $($e,)+)
+    };
+}
+
+/// Wait for the first of several futures and run the handler of its branch,
+/// the other futures are dropped.
+///
+/// ```text
+/// runtime::select! {
+///     Some(message) = rx.recv() => println!("got {message}"),
+///     _ = AsyncTimer::new(timeout), if has_timeout => println!("timed out"),
+///     else => println!("channel closed"),
+/// }
+/// ```
+///
+/// Every branch is `<pattern> = <future> [, if <condition>] => <handler>`.
+/// A branch whose condition is false isn't polled, a branch whose output
+/// doesn't match the pattern is disabled and the others are polled on.
+/// Once all branches are disabled the `else` arm runs, without one that
+/// panics. A `default` arm runs if no future is ready on the first poll.
+///
+/// Which future is polled first is random, so one that is always ready
+/// can't starve the others. Start with `biased;` to poll them in order.
+#[macro_export]
+macro_rules! select {
+    (@count) => { 0 };
+    (@count _ $($t:tt)*) => { 1 + $crate::select!(@count $($t)*) };
+
+    // The pattern is matched against a reference to the output first, so
+    // `mut` and `ref` are removed, also from nested groups
+    (@clean [] [$($out:tt)*]) => { $($out)* };
+    (@clean [$($stack:tt)*] [$($out:tt)*] & mut $($rest:tt)*) => {
+        $crate::select!(@clean [$($stack)*] [$($out)* & mut] $($rest)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] ref $($rest:tt)*) => {
+        $crate::select!(@clean [$($stack)*] [$($out)*] $($rest)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] mut $($rest:tt)*) => {
+        $crate::select!(@clean [$($stack)*] [$($out)*] $($rest)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] ( $($inner:tt)* ) $($rest:tt)*) => {
+        $crate::select!(@clean [(paren [$($out)*] [$($rest)*]) $($stack)*] [] $($inner)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] [ $($inner:tt)* ] $($rest:tt)*) => {
+        $crate::select!(@clean [(bracket [$($out)*] [$($rest)*]) $($stack)*] [] $($inner)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] { $($inner:tt)* } $($rest:tt)*) => {
+        $crate::select!(@clean [(brace [$($out)*] [$($rest)*]) $($stack)*] [] $($inner)*)
+    };
+    (@clean [(paren [$($parent:tt)*] [$($rest:tt)*]) $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::select!(@clean [$($stack)*] [$($parent)* ( $($out)* )] $($rest)*)
+    };
+    (@clean [(bracket [$($parent:tt)*] [$($rest:tt)*]) $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::select!(@clean [$($stack)*] [$($parent)* [ $($out)* ]] $($rest)*)
+    };
+    (@clean [(brace [$($parent:tt)*] [$($rest:tt)*]) $($stack:tt)*] [$($out:tt)*]) => {
+        $crate::select!(@clean [$($stack)*] [$($parent)* { $($out)* }] $($rest)*)
+    };
+    (@clean [$($stack:tt)*] [$($out:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::select!(@clean [$($stack)*] [$($out)* $t] $($rest)*)
+    };
+
+    (@default []) => { ::std::task::Poll::Pending };
+    (@default [$($default:tt)+]) => { ::std::task::Poll::Ready(Some(usize::MAX)) };
+    (@run_default []) => { unreachable!() };
+    (@run_default [$default:expr]) => { $default };
+    (@run_else []) => { panic!("all branches of select! are disabled and there is no else arm") };
+    (@run_else [$else:expr]) => { $else };
+
+    (@expand {
+        $biased:tt;
+        ( $($count:tt)* );
+        $( ( $($skip:tt)* ) [ $($p:tt)* ] $f:expr, $c:expr, $h:expr; )*
+    } [$($default:tt)*] [$($else:tt)*]) => {{
+        const BRANCHES: usize = $crate::select!(@count $($count)*);
+        let mut disabled: [bool; BRANCHES] = [ $( !$c, )* ];
+        // The futures are dropped at the end of this block, the handler might
+        // need what they borrowed
+        let (branch, mut outputs) = {
+            let mut futures = ( $( ::std::pin::pin!($crate::future::MaybeDone::new($f)), )* );
+            let branch: Option<usize> = ::std::future::poll_fn(|cx| {
+                let start = if $biased { 0 } else { $crate::future::random_index(BRANCHES) };
+                for offset in 0..BRANCHES {
+                    #[allow(clippy::modulo_one)]
+                    let index = (start + offset) % BRANCHES;
+                    if disabled[index] {
+                        continue;
+                    }
+                    $(
+                        if index == $crate::select!(@count $($skip)*) {
+                            let ( $($skip,)* fut, .. ) = &mut futures;
+                            if fut.as_mut().poll_done(cx).is_ready() {
+                                match fut.output() {
+                                    #[allow(unused_variables)]
+                                    Some($crate::select!(@clean [] [] $($p)*)) => {
+                                        return ::std::task::Poll::Ready(Some(index));
+                                    }
+                                    _ => disabled[index] = true,
+                                }
+                            }
+                        }
+                    )*
+                }
+                if disabled.iter().all(|disabled| *disabled) {
+                    return ::std::task::Poll::Ready(None);
+                }
+                $crate::select!(@default [$($default)*])
+            })
+            .await;
+            // Only the output of the branch that won
+            let outputs = ( $({
+                let ( $($skip,)* fut, .. ) = &mut futures;
+                if branch == Some($crate::select!(@count $($skip)*)) {
+                    fut.as_mut().take_output()
+                } else {
+                    None
+                }
+            }, )* );
+            (branch, outputs)
+        };
+        match branch {
+            $(
+                Some(index) if index == $crate::select!(@count $($skip)*) => {
+                    let ( $($skip,)* output, .. ) = &mut outputs;
+                    match output.take().unwrap() {
+                        $($p)* => $h,
+                        #[allow(unreachable_patterns)]
+                        _ => unreachable!(),
+                    }
+                }
+            )*
+            Some(_) => $crate::select!(@run_default [$($default)*]),
+            None => $crate::select!(@run_else [$($else)*]),
+        }
+    }};
+
+    // Parse the branches one after the other
+    (@branches {$($s:tt)*} []) => {
+        $crate::select!(@expand {$($s)*} [] [])
+    };
+    (@branches {$($s:tt)*} [] else => $else:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [] [$else])
+    };
+    (@branches {$($s:tt)*} [] default => $default:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [$default] [])
+    };
+    (@branches {$($s:tt)*} [] default => $default:block $(,)? else => $else:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [$default] [$else])
+    };
+    (@branches {$($s:tt)*} [] default => $default:expr, else => $else:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [$default] [$else])
+    };
+    (@branches {$($s:tt)*} [] else => $else:block $(,)? default => $default:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [$default] [$else])
+    };
+    (@branches {$($s:tt)*} [] else => $else:expr, default => $default:expr $(,)?) => {
+        $crate::select!(@expand {$($s)*} [$default] [$else])
+    };
+    (@branches {$($s:tt)*} [$($p:tt)+] = $($rest:tt)*) => {
+        $crate::select!(@future {$($s)*} [$($p)+] $($rest)*)
+    };
+    (@branches {$($s:tt)*} [$($p:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::select!(@branches {$($s)*} [$($p)* $t] $($rest)*)
+    };
+    (@future {$($s:tt)*} [$($p:tt)+] $f:expr, if $c:expr => $($rest:tt)*) => {
+        $crate::select!(@handler {$($s)*} [$($p)+] $f, $c; $($rest)*)
+    };
+    (@future {$($s:tt)*} [$($p:tt)+] $f:expr => $($rest:tt)*) => {
+        $crate::select!(@handler {$($s)*} [$($p)+] $f, true; $($rest)*)
+    };
+    (@handler {$($s:tt)*} [$($p:tt)+] $f:expr, $c:expr; $h:block $(,)? $($rest:tt)*) => {
+        $crate::select!(@push {$($s)*} [$($p)+] $f, $c, $h; $($rest)*)
+    };
+    (@handler {$($s:tt)*} [$($p:tt)+] $f:expr, $c:expr; $h:expr, $($rest:tt)*) => {
+        $crate::select!(@push {$($s)*} [$($p)+] $f, $c, $h; $($rest)*)
+    };
+    (@handler {$($s:tt)*} [$($p:tt)+] $f:expr, $c:expr; $h:expr) => {
+        $crate::select!(@push {$($s)*} [$($p)+] $f, $c, $h;)
+    };
+    (@push { $biased:tt; ( $($count:tt)* ); $($branches:tt)* }
+        [$($p:tt)+] $f:expr, $c:expr, $h:expr; $($rest:tt)*) => {
+        $crate::select!(@branches {
+            $biased;
+            ( $($count)* _ );
+            $($branches)* ( $($count)* ) [$($p)+] $f, $c, $h;
+        } [] $($rest)*)
+    };
+
+    (biased; $($t:tt)*) => {
+        $crate::select!(@branches { true; (); } [] $($t)*)
+    };
+    ($($t:tt)*) => {
+        $crate::select!(@branches { false; (); } [] $($t)*)
+    };
+}
+
+/// Build a `Stream` from an async block, see `stream::generate`.
+///
+/// ```text
+/// let numbers = runtime::async_stream!(y => {
+///     for i in 0..3 {
+///         y.yield_item(i).await;
+///     }
+/// });
+/// ```
+#[macro_export]
+macro_rules! async_stream {
+    ($yielder:ident => $body:block) => {
+        $crate::stream::generate(move |$yielder| async move $body)
+    };
+}
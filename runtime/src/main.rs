@@ -1,4 +1,4 @@
-use runtime::Executor;
+use runtime::{Executor, time};
 
 use async_timer::AsyncTimer;
 
@@ -17,18 +17,28 @@ async fn timering2() {
 }
 
 async fn looping_timer() {
+    println!("Starting a 5 second interval...");
+    // The deadlines are fixed, the time spent between ticks doesn't add up
+    let mut interval =
+        time::interval_at(time::now() + Duration::from_secs(5), Duration::from_secs(5));
     for i in 1..10 {
-        println!("Starting a {i} second timer...");
-        AsyncTimer::new(Duration::from_secs(i)).await;
-        println!("{i} second timer elapsed!");
+        interval.tick().await;
+        println!("Interval tick {i} after {} seconds!", i * 5);
     }
 }
 
 fn main() {
-    let mut executor = Executor::new();
+    // With `--simulate`, the 45 seconds of timers pass on a virtual clock
+    let simulate = std::env::args().any(|arg| arg == "--simulate");
+    let mut executor = if simulate {
+        Executor::simulated(0)
+    } else {
+        Executor::new()
+    };
+    let start = time::now();
     executor.schedule(timering());
     executor.schedule(timering2());
     executor.schedule(looping_timer());
     executor.block();
-    println!("End of program!");
+    println!("End of program after {:?}!", time::now() - start);
 }
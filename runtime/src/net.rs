@@ -0,0 +1,273 @@
+//! Asynchronous TCP sockets.
+use crate::{
+    coop, ffi,
+    io::{AsyncFd, AsyncRead, AsyncWrite},
+};
+use std::{
+    io::{self, Read, Write},
+    mem,
+    net::{self, Shutdown, SocketAddr, ToSocketAddrs},
+    os::fd::{AsRawFd, FromRawFd, RawFd},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+/// Resolve a host name (with port, e.g. `("example.com", 80)`) on the
+/// blocking pool, as the system resolver can only block
+pub async fn lookup_host(host: impl ToSocketAddrs + Send + 'static) -> io::Result<Vec<SocketAddr>> {
+    let addrs = crate::spawn_blocking(move || Ok(host.to_socket_addrs()?.collect())).await;
+    addrs.map_err(io::Error::other)?
+}
+
+/// A TCP connection whose reads and writes wait on the reactor instead of blocking
+pub struct TcpStream {
+    io: AsyncFd<net::TcpStream>,
+}
+
+impl TcpStream {
+    /// Connect to the first address that accepts the connection.
+    /// Note that resolving a host name here blocks, `lookup_host` doesn't.
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in addr.to_socket_addrs()? {
+            match Self::connect_addr(addr).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )
+        }))
+    }
+
+    async fn connect_addr(addr: SocketAddr) -> io::Result<TcpStream> {
+        let domain = match addr {
+            SocketAddr::V4(_) => ffi::AF_INET,
+            SocketAddr::V6(_) => ffi::AF_INET6,
+        };
+        let ty = ffi::SOCK_STREAM | ffi::SOCK_NONBLOCK | ffi::SOCK_CLOEXEC;
+        let fd = unsafe { ffi::socket(domain, ty, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // From here on the fd is closed when `stream` is dropped
+        let stream = unsafe { net::TcpStream::from_raw_fd(fd) };
+        let res = match addr {
+            SocketAddr::V4(addr) => {
+                let raw = ffi::SockAddrIn {
+                    sin_family: ffi::AF_INET as u16,
+                    sin_port: addr.port().to_be(),
+                    sin_addr: addr.ip().octets(),
+                    sin_zero: [0; 8],
+                };
+                let len = mem::size_of_val(&raw) as u32;
+                unsafe { ffi::connect(fd, &raw as *const _ as *const u8, len) }
+            }
+            SocketAddr::V6(addr) => {
+                let raw = ffi::SockAddrIn6 {
+                    sin6_family: ffi::AF_INET6 as u16,
+                    sin6_port: addr.port().to_be(),
+                    sin6_flowinfo: addr.flowinfo(),
+                    sin6_addr: addr.ip().octets(),
+                    sin6_scope_id: addr.scope_id(),
+                };
+                let len = mem::size_of_val(&raw) as u32;
+                unsafe { ffi::connect(fd, &raw as *const _ as *const u8, len) }
+            }
+        };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(ffi::EINPROGRESS) {
+                return Err(err);
+            }
+        }
+        // The socket becomes writable once the connection is established or failed
+        let stream = TcpStream {
+            io: AsyncFd::new(stream)?,
+        };
+        stream.io.writable().await?;
+        if let Some(err) = stream.io.get_ref().take_error()? {
+            return Err(err);
+        }
+        Ok(stream)
+    }
+
+    /// Take over a connected std socket, it is switched to non-blocking mode
+    pub fn from_std(stream: net::TcpStream) -> io::Result<TcpStream> {
+        stream.set_nonblocking(true)?;
+        Ok(TcpStream {
+            io: AsyncFd::new(stream)?,
+        })
+    }
+
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        std::future::poll_fn(|cx| self.poll_read(cx, buf)).await
+    }
+
+    pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        std::future::poll_fn(|cx| self.poll_write(cx, buf)).await
+    }
+
+    pub async fn write_all(&self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf).await? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    pub fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        coop::poll_budgeted(cx, |cx| {
+            loop {
+                let mut guard = ready!(self.io.poll_read_ready(cx))?;
+                if let Ok(result) = guard.try_io(|io| io.get_ref().read(buf)) {
+                    return Poll::Ready(result);
+                }
+            }
+        })
+    }
+
+    pub fn poll_write(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        coop::poll_budgeted(cx, |cx| {
+            loop {
+                let mut guard = ready!(self.io.poll_write_ready(cx))?;
+                if let Ok(result) = guard.try_io(|io| io.get_ref().write(buf)) {
+                    return Poll::Ready(result);
+                }
+            }
+        })
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().shutdown(how)
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().peer_addr()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.io.get_ref().set_nodelay(nodelay)
+    }
+}
+
+impl AsyncRead for TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        TcpStream::poll_read(&self, cx, buf)
+    }
+}
+
+// Reads and writes only need `&TcpStream`, so a shared reference can be used
+// as reader and writer at the same time
+impl AsyncRead for &TcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        TcpStream::poll_read(&self, cx, buf)
+    }
+}
+
+impl AsyncWrite for TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        TcpStream::poll_write(&self, cx, buf)
+    }
+
+    /// Writes go straight to the socket, there is nothing to flush
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncWrite for &TcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        TcpStream::poll_write(&self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.shutdown(Shutdown::Write))
+    }
+}
+
+impl AsRawFd for TcpStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.as_raw_fd()
+    }
+}
+
+/// A TCP socket server whose `accept` waits on the reactor
+pub struct TcpListener {
+    io: AsyncFd<net::TcpListener>,
+}
+
+impl TcpListener {
+    /// Bind to `addr`, binding itself never blocks so this is not async
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<TcpListener> {
+        Self::from_std(net::TcpListener::bind(addr)?)
+    }
+
+    /// Take over a bound std listener, it is switched to non-blocking mode
+    pub fn from_std(listener: net::TcpListener) -> io::Result<TcpListener> {
+        listener.set_nonblocking(true)?;
+        Ok(TcpListener {
+            io: AsyncFd::new(listener)?,
+        })
+    }
+
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        std::future::poll_fn(|cx| self.poll_accept(cx)).await
+    }
+
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        coop::poll_budgeted(cx, |cx| {
+            loop {
+                let mut guard = ready!(self.io.poll_read_ready(cx))?;
+                if let Ok(result) = guard.try_io(|io| io.get_ref().accept()) {
+                    return Poll::Ready(
+                        result.and_then(|(stream, addr)| Ok((TcpStream::from_std(stream)?, addr))),
+                    );
+                }
+            }
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+}
+
+impl AsRawFd for TcpListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.as_raw_fd()
+    }
+}
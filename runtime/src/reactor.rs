@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    io,
+    os::fd::AsRawFd,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context, Poll, Waker},
+    thread,
+};
+use timer_event_queue::{
+    ffi,
+    poll::{self, Registry},
+};
+
+/// Readiness flags that make a read attempt worthwhile
+pub(crate) const READABLE: u32 =
+    (ffi::EPOLL_IN | ffi::EPOLLRDHUP | ffi::EPOLLHUP | ffi::EPOLLERR) as u32;
+/// Readiness flags that make a write attempt worthwhile
+pub(crate) const WRITABLE: u32 = (ffi::EPOLLOUT | ffi::EPOLLHUP | ffi::EPOLLERR) as u32;
+
+/// Readiness that was observed for a source. The `tick` identifies the
+/// event that set it, so clearing it again can't swallow a newer event.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadyEvent {
+    pub(crate) ready: u32,
+    tick: usize,
+}
+
+#[derive(Default)]
+struct IoState {
+    readiness: u32,
+    tick: usize,
+    // Every task that waits for the direction, several tasks can wait on the
+    // same source (e.g. through `&TcpStream`) and all of them are woken
+    read_wakers: Vec<Waker>,
+    write_wakers: Vec<Waker>,
+}
+
+/// The reactor owns the event queue and runs the event loop on its own thread.
+/// Whenever the OS reports an event, the readiness of the source is stored and the
+/// waker of the task that waits for it is woken, so the executor polls it again.
+pub(crate) struct Reactor {
+    registry: Registry,
+    sources: Mutex<HashMap<usize, IoState>>,
+    next_token: AtomicUsize,
+}
+
+static REACTOR: OnceLock<Reactor> = OnceLock::new();
+
+/// Get the reactor, starting the event loop on first use
+pub(crate) fn reactor() -> &'static Reactor {
+    REACTOR.get_or_init(|| {
+        let poll = poll::Poll::new().expect("Failed to create the event queue");
+        let registry = poll.registry().clone();
+        thread::Builder::new()
+            .name("reactor".to_string())
+            .spawn(move || event_loop(poll))
+            .expect("Failed to spawn the reactor thread");
+        Reactor {
+            registry,
+            sources: Mutex::new(HashMap::new()),
+            next_token: AtomicUsize::new(0),
+        }
+    })
+}
+
+fn event_loop(mut poll: poll::Poll) {
+    let mut events = Vec::with_capacity(128);
+    loop {
+        if let Err(e) = poll.poll(&mut events, None) {
+            if e.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            panic!("Reactor failed to wait for events: {e}");
+        }
+        let mut wakers = Vec::new();
+        let mut sources = reactor().sources.lock().unwrap();
+        for event in &events {
+            // The source could have been deregistered in the meantime
+            let Some(state) = sources.get_mut(&event.token()) else {
+                continue;
+            };
+            let ready = event.events();
+            state.readiness |= ready;
+            state.tick = state.tick.wrapping_add(1);
+            if ready & READABLE != 0 {
+                wakers.append(&mut state.read_wakers);
+            }
+            if ready & WRITABLE != 0 {
+                wakers.append(&mut state.write_wakers);
+            }
+        }
+        drop(sources);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Reactor {
+    /// Register a source for edge-triggered read and write events and return its token
+    pub(crate) fn register(&self, source: &impl AsRawFd) -> io::Result<usize> {
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        self.sources
+            .lock()
+            .unwrap()
+            .insert(token, IoState::default());
+        let interests = ffi::EPOLL_IN | ffi::EPOLLOUT | ffi::EPOLLRDHUP | ffi::EPOLLET;
+        if let Err(e) = self.registry.register(source, token, interests) {
+            self.sources.lock().unwrap().remove(&token);
+            return Err(e);
+        }
+        Ok(token)
+    }
+
+    pub(crate) fn deregister(&self, source: &impl AsRawFd, token: usize) -> io::Result<()> {
+        self.sources.lock().unwrap().remove(&token);
+        self.registry.deregister(source)
+    }
+
+    /// Check whether the source is ready for `interest` (`READABLE` or `WRITABLE`),
+    /// if not, the waker is stored and woken by the event loop once it is.
+    /// Fails if the source is not registered (anymore).
+    pub(crate) fn poll_ready(
+        &self,
+        token: usize,
+        interest: u32,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<ReadyEvent>> {
+        let mut sources = self.sources.lock().unwrap();
+        let Some(state) = sources.get_mut(&token) else {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the source is not registered with the reactor",
+            )));
+        };
+        if state.readiness & interest != 0 {
+            return Poll::Ready(Ok(ReadyEvent {
+                ready: state.readiness & interest,
+                tick: state.tick,
+            }));
+        }
+        let wakers = if interest == WRITABLE {
+            &mut state.write_wakers
+        } else {
+            &mut state.read_wakers
+        };
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    /// Forget the readiness of `event` after the source returned `WouldBlock`.
+    /// Errors and hang ups are final and are never cleared.
+    pub(crate) fn clear_readiness(&self, token: usize, event: ReadyEvent) {
+        let mut sources = self.sources.lock().unwrap();
+        if let Some(state) = sources.get_mut(&token)
+            && state.tick == event.tick
+        {
+            let terminal = (ffi::EPOLLHUP | ffi::EPOLLERR | ffi::EPOLLRDHUP) as u32;
+            state.readiness &= !(event.ready & !terminal);
+        }
+    }
+}
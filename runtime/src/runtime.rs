@@ -1,4 +1,10 @@
+use crate::{
+    coop, future,
+    task::{Dispatch, Priority},
+    time,
+};
 use std::{
+    cell::RefCell,
     collections::{HashMap, VecDeque},
     future::Future,
     pin::Pin,
@@ -9,56 +15,204 @@ use std::{
 
 type Task = Pin<Box<dyn Future<Output = ()>>>;
 
+// Default number of times a ready task is passed over before it runs anyway
+const DEFAULT_AGING: u32 = 32;
+
+thread_local! {
+    // Tasks spawned by other tasks, `Executor::block` picks them up after each poll.
+    // `None` while no executor is running on this thread.
+    static SPAWNED: RefCell<Option<Vec<(Priority, Task)>>> = const { RefCell::new(None) };
+}
+
+/// Spawn a new task onto the executor that is running the current task
+pub fn spawn(future: impl Future<Output = ()> + 'static) {
+    spawn_with_priority(Priority::Normal, future);
+}
+
+pub(crate) fn spawn_with_priority(priority: Priority, future: impl Future<Output = ()> + 'static) {
+    SPAWNED.with_borrow_mut(|spawned| {
+        spawned
+            .as_mut()
+            .expect("spawn must be called from a task running on an Executor")
+            .push((priority, Box::pin(future)));
+    });
+}
+
 pub struct MyWaker {
     task_id: usize,
-    ready_queue: Arc<Mutex<VecDeque<usize>>>,
+    priority: Priority,
+    ready_queue: Arc<Mutex<ReadyQueue>>,
     thread: thread::Thread,
 }
 
 impl Wake for MyWaker {
     fn wake(self: Arc<Self>) {
-        self.ready_queue.lock().unwrap().push_back(self.task_id);
+        self.ready_queue
+            .lock()
+            .unwrap()
+            .push(self.priority, self.task_id);
         self.thread.unpark();
     }
 }
 
 pub struct Executor {
-    tasks: HashMap<usize, Task>,
-    ready_queue: Arc<Mutex<VecDeque<usize>>>,
+    tasks: HashMap<usize, (Priority, Task)>,
+    // One waker per task, so futures can tell with `Waker::will_wake` whether they were
+    // polled by the same task again
+    wakers: HashMap<usize, Waker>,
+    ready_queue: Arc<Mutex<ReadyQueue>>,
     next_id: usize,
+    // Set if `simulated` paused the clock, it is resumed on drop
+    paused_clock: bool,
+}
+
+/// One FIFO queue of ready task ids per priority
+pub(crate) struct ReadyQueue {
+    // Indexed by `Priority::index`
+    queues: [VecDeque<usize>; 3],
+    dispatch: Dispatch,
+    // Tasks a queue may still dispatch in this round of `Dispatch::Weighted`
+    credits: [u32; 3],
+    aging: u32,
+    // How often a non-empty queue was passed over since it was last served
+    passed_over: [u32; 3],
+    // Set by `Executor::simulated`, picks a random task of the queue instead
+    // of the first one
+    rng: Option<u64>,
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Executor {
+    /// Strict priority dispatch, a ready task runs at the latest after it
+    /// was passed over 32 times
     pub fn new() -> Self {
         Executor {
             tasks: HashMap::new(),
-            ready_queue: Arc::new(Mutex::new(VecDeque::new())),
+            wakers: HashMap::new(),
+            ready_queue: Arc::new(Mutex::new(ReadyQueue::new(Dispatch::Strict, DEFAULT_AGING))),
             next_id: 0,
+            paused_clock: false,
+        }
+    }
+
+    /// An executor for tests, with the clock of this thread paused (see
+    /// `time::pause`): instead of waiting for a timer, it skips ahead to the
+    /// next deadline whenever no task is ready.
+    ///
+    /// Tasks of the same priority run in an order picked by `seed` instead
+    /// of the order they were woken in, and so do the branches of `select!`
+    /// and `race`. A run can be repeated exactly with the same seed, and
+    /// other seeds try other orders. Only tasks woken from other threads,
+    /// e.g. by I/O or `spawn_blocking`, can still make runs differ.
+    ///
+    /// Dropping the executor resumes the real clock, unless it was paused
+    /// before.
+    pub fn simulated(seed: u64) -> Self {
+        let paused_clock = !time::is_paused();
+        time::pause();
+        future::seed_random_index(seed);
+        let mut executor = Self::new();
+        executor.paused_clock = paused_clock;
+        executor.ready_queue.lock().unwrap().rng = Some(future::seed_state(!seed));
+        executor
+    }
+
+    /// How the next task is picked among the ready tasks of different
+    /// priorities. Panics on a weight of `0`.
+    pub fn dispatch(self, dispatch: Dispatch) -> Self {
+        if let Dispatch::Weighted { high, normal, low } = dispatch {
+            assert!(
+                high > 0 && normal > 0 && low > 0,
+                "dispatch weights must be at least 1"
+            );
         }
+        self.ready_queue.lock().unwrap().set_dispatch(dispatch);
+        self
+    }
+
+    /// Starvation protection: a ready task runs at the latest after tasks
+    /// of other priorities were picked `limit` times in a row, no matter
+    /// what the dispatch says. Panics if `limit` is `0`.
+    pub fn aging(self, limit: u32) -> Self {
+        assert!(limit > 0, "the aging limit must be at least 1");
+        self.ready_queue.lock().unwrap().aging = limit;
+        self
     }
 
+    /// Schedule a task with `Priority::Normal`, see `task::Builder` for others
     pub fn schedule(&mut self, future: impl Future<Output = ()> + 'static) {
-        let pinned_future = Box::pin(future);
-        self.tasks.insert(self.next_id, pinned_future);
-        self.ready_queue.lock().unwrap().push_back(self.next_id);
+        self.schedule_with_priority(Priority::Normal, future);
+    }
+
+    pub(crate) fn schedule_with_priority(
+        &mut self,
+        priority: Priority,
+        future: impl Future<Output = ()> + 'static,
+    ) {
+        self.insert(priority, Box::pin(future));
+    }
+
+    fn insert(&mut self, priority: Priority, future: Task) {
+        self.tasks.insert(self.next_id, (priority, future));
+        self.ready_queue
+            .lock()
+            .unwrap()
+            .push(priority, self.next_id);
         self.next_id += 1;
     }
 
     pub fn block(&mut self) {
+        SPAWNED.with_borrow_mut(|spawned| {
+            assert!(spawned.is_none(), "Executor::block must not be nested");
+            *spawned = Some(Vec::new());
+        });
+        self.run();
+        SPAWNED.set(None);
+    }
+
+    fn run(&mut self) {
         loop {
-            while let Some(id) = self.ready_queue.lock().unwrap().pop_front() {
-                let mut future = self.tasks.remove(&id).unwrap();
-                let waker: Waker = self.from_id(id).into();
+            while let Some(id) = self.pop_ready() {
+                // A task can be woken more than once, even after it completed
+                let Some((priority, mut future)) = self.tasks.remove(&id) else {
+                    continue;
+                };
+                let waker = match self.wakers.get(&id) {
+                    Some(waker) => waker.clone(),
+                    None => {
+                        let waker: Waker = self.waker_for(id, priority).into();
+                        self.wakers.insert(id, waker.clone());
+                        waker
+                    }
+                };
                 let mut ctx = Context::from_waker(&waker);
-                match future.as_mut().poll(&mut ctx) {
-                    Poll::Ready(_) => (),
+                match coop::budget(|| future.as_mut().poll(&mut ctx)) {
+                    Poll::Ready(_) => {
+                        self.wakers.remove(&id);
+                    }
                     Poll::Pending => {
-                        self.tasks.insert(id, future);
+                        self.tasks.insert(id, (priority, future));
                     }
                 };
+                let spawned =
+                    SPAWNED.with_borrow_mut(|spawned| spawned.as_mut().map(std::mem::take));
+                for (priority, future) in spawned.into_iter().flatten() {
+                    self.insert(priority, future);
+                }
             }
             let tasks_count = self.tasks.len();
             let thread_name = thread::current().name().unwrap_or_default().to_string();
+            if tasks_count > 0
+                && let Some(wait) = time::until_next_deadline()
+            {
+                time::advance(wait);
+                continue;
+            }
             if tasks_count > 0 {
                 println!(
                     "⏸️ Waiting for tasks to be ready. {tasks_count} tasks remaining. Parking thread {thread_name}.",
@@ -74,11 +228,109 @@ impl Executor {
         }
     }
 
-    fn from_id(&self, id: usize) -> Arc<MyWaker> {
+    fn pop_ready(&self) -> Option<usize> {
+        // The lock must not be held while the task is polled, as its waker might be called right away
+        self.ready_queue.lock().unwrap().pop()
+    }
+
+    fn waker_for(&self, id: usize, priority: Priority) -> Arc<MyWaker> {
         Arc::new(MyWaker {
             task_id: id,
+            priority,
             ready_queue: self.ready_queue.clone(),
             thread: thread::current(),
         })
     }
 }
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        if self.paused_clock {
+            time::resume();
+        }
+    }
+}
+
+impl ReadyQueue {
+    fn new(dispatch: Dispatch, aging: u32) -> Self {
+        let mut queue = ReadyQueue {
+            queues: Default::default(),
+            dispatch,
+            credits: [0; 3],
+            aging,
+            passed_over: [0; 3],
+            rng: None,
+        };
+        queue.set_dispatch(dispatch);
+        queue
+    }
+
+    fn set_dispatch(&mut self, dispatch: Dispatch) {
+        self.dispatch = dispatch;
+        self.credits = self.weights();
+    }
+
+    fn weights(&self) -> [u32; 3] {
+        match self.dispatch {
+            Dispatch::Strict => [0; 3],
+            Dispatch::Weighted { high, normal, low } => [high, normal, low],
+        }
+    }
+
+    fn push(&mut self, priority: Priority, id: usize) {
+        self.queues[priority.index()].push_back(id);
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let level = self.pick()?;
+        for other in 0..3 {
+            if other != level && !self.queues[other].is_empty() {
+                self.passed_over[other] += 1;
+            }
+        }
+        self.passed_over[level] = 0;
+        let queue = &mut self.queues[level];
+        match &mut self.rng {
+            Some(rng) => {
+                *rng = future::xorshift(*rng);
+                let index = (*rng % queue.len() as u64) as usize;
+                queue.swap_remove_back(index)
+            }
+            None => queue.pop_front(),
+        }
+    }
+
+    /// The priorities that have ready tasks, highest first
+    fn ready_levels(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..3).filter(|level| !self.queues[*level].is_empty())
+    }
+
+    /// The queue to take the next task from
+    fn pick(&mut self) -> Option<usize> {
+        let highest = self.ready_levels().next()?;
+        // The queue that waited longest beyond the aging limit goes first
+        let starving = self
+            .ready_levels()
+            .filter(|level| self.passed_over[*level] >= self.aging)
+            .max_by_key(|level| self.passed_over[*level]);
+        if let Some(level) = starving {
+            return Some(level);
+        }
+        match self.dispatch {
+            Dispatch::Strict => Some(highest),
+            Dispatch::Weighted { .. } => {
+                // Every queue gets its weight in turns per round, a round ends
+                // when no ready queue has credits left
+                if self.ready_levels().all(|level| self.credits[level] == 0) {
+                    self.credits = self.weights();
+                }
+                let level = self
+                    .ready_levels()
+                    .find(|level| self.credits[*level] > 0)
+                    .unwrap();
+                self.credits[level] -= 1;
+                Some(level)
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! The write side of `Stream`: the `Sink` trait for anything values can be
+//! sent into, with backpressure, and the `SinkExt` helpers.
+//!
+//! ```text
+//! let (tx, mut rx) = mpsc::channel(4);
+//! let mut tx = tx.with(|n: u32| async move { Ok(n.to_string()) }).buffer(16);
+//! tx.send_all(&mut stream::iter(0..100)).await?;
+//! tx.close().await?;
+//! ```
+//!
+//! Sending takes up to three steps: `poll_ready` waits until the sink can
+//! take another value, `start_send` hands it over and `poll_flush` waits
+//! until everything handed over has actually been delivered.
+mod adapters;
+
+pub use adapters::{Buffer, Fanout, With};
+
+use crate::stream::Stream;
+use std::{
+    future::{Future, poll_fn},
+    ops::DerefMut,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+/// Something values can be sent into, e.g. a channel or a connection
+pub trait Sink<Item> {
+    type Error;
+
+    /// `Ready(Ok(()))` once the sink can take a value with `start_send`.
+    /// Pending while it's full, the task is woken when there is room again.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+
+    /// Hand over a value, only allowed right after `poll_ready` returned
+    /// `Ready(Ok(()))`. The value might just be buffered.
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error>;
+
+    /// Wait until all values handed over were delivered
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+
+    /// Flush and then shut the sink down, no values can be sent after this
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+}
+
+impl<S: Sink<Item> + Unpin + ?Sized, Item> Sink<Item> for &mut S {
+    type Error = S::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Item) -> Result<(), S::Error> {
+        Pin::new(&mut **self).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
+
+impl<S: Sink<Item> + Unpin + ?Sized, Item> Sink<Item> for Box<S> {
+    type Error = S::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Item) -> Result<(), S::Error> {
+        Pin::new(&mut **self).start_send(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        Pin::new(&mut **self).poll_close(cx)
+    }
+}
+
+impl<P, Item> Sink<Item> for Pin<P>
+where
+    P: DerefMut<Target: Sink<Item>>,
+{
+    type Error = <P::Target as Sink<Item>>::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.as_deref_mut().poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error> {
+        self.as_deref_mut().start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.as_deref_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.as_deref_mut().poll_close(cx)
+    }
+}
+
+/// Helpers for every `Sink`
+pub trait SinkExt<Item>: Sink<Item> {
+    /// Wait until the sink is ready, send `item` and flush it
+    fn send<'a>(&'a mut self, item: Item) -> impl Future<Output = Result<(), Self::Error>> + 'a
+    where
+        Item: 'a,
+        Self: Unpin,
+    {
+        async move {
+            poll_fn(|cx| Pin::new(&mut *self).poll_ready(cx)).await?;
+            Pin::new(&mut *self).start_send(item)?;
+            self.flush().await
+        }
+    }
+
+    /// Send all items of `stream`, flushing whenever it has nothing to give
+    /// right now and once it ended. The sink is not closed.
+    fn send_all<'a, St>(
+        &'a mut self,
+        stream: &'a mut St,
+    ) -> impl Future<Output = Result<(), Self::Error>> + 'a
+    where
+        St: Stream<Item = Item> + Unpin + ?Sized,
+        Item: 'a,
+        Self: Unpin,
+    {
+        // An item taken from the stream while the sink was full
+        let mut buffered = None;
+        poll_fn(move |cx| {
+            loop {
+                if let Some(item) = buffered.take() {
+                    match Pin::new(&mut *self).poll_ready(cx)? {
+                        Poll::Ready(()) => Pin::new(&mut *self).start_send(item)?,
+                        Poll::Pending => {
+                            buffered = Some(item);
+                            return Poll::Pending;
+                        }
+                    }
+                }
+                match Pin::new(&mut *stream).poll_next(cx) {
+                    Poll::Ready(Some(item)) => buffered = Some(item),
+                    Poll::Ready(None) => return Pin::new(&mut *self).poll_flush(cx),
+                    Poll::Pending => {
+                        ready!(Pin::new(&mut *self).poll_flush(cx))?;
+                        return Poll::Pending;
+                    }
+                }
+            }
+        })
+    }
+
+    fn flush(&mut self) -> impl Future<Output = Result<(), Self::Error>> + '_
+    where
+        Self: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_flush(cx))
+    }
+
+    fn close(&mut self) -> impl Future<Output = Result<(), Self::Error>> + '_
+    where
+        Self: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_close(cx))
+    }
+
+    /// Turn the values sent into this sink with an async function first. The
+    /// function's error type must be able to hold the sink's errors.
+    fn with<U, Fut, F, E>(self, f: F) -> With<Self, Item, U, Fut, F>
+    where
+        F: FnMut(U) -> Fut,
+        Fut: Future<Output = Result<Item, E>>,
+        E: From<Self::Error>,
+        Self: Sized,
+    {
+        With::new(self, f)
+    }
+
+    /// Buffer up to `capacity` values in front of the sink, so senders only
+    /// wait once the buffer is full
+    fn buffer(self, capacity: usize) -> Buffer<Self, Item>
+    where
+        Self: Sized,
+    {
+        Buffer::new(self, capacity)
+    }
+
+    /// Send a clone of every value into both sinks, ready once both are
+    fn fanout<Si>(self, other: Si) -> Fanout<Self, Si>
+    where
+        Item: Clone,
+        Si: Sink<Item, Error = Self::Error>,
+        Self: Sized,
+    {
+        Fanout::new(self, other)
+    }
+}
+
+impl<S: Sink<Item> + ?Sized, Item> SinkExt<Item> for S {}
@@ -0,0 +1,216 @@
+use super::Sink;
+use std::{
+    collections::VecDeque,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+pub struct With<Si, Item, U, Fut, F> {
+    sink: Pin<Box<Si>>,
+    f: F,
+    // Turns the last value into an `Item`
+    pending: Option<Pin<Box<Fut>>>,
+    _marker: PhantomData<fn(U) -> Item>,
+}
+
+pub struct Buffer<Si, Item> {
+    sink: Pin<Box<Si>>,
+    buffer: VecDeque<Item>,
+    capacity: usize,
+}
+
+pub struct Fanout<A, B> {
+    a: Pin<Box<A>>,
+    b: Pin<Box<B>>,
+}
+
+// Only the boxed sinks are pinned, never the closures or buffered values
+impl<Si, Item, U, Fut, F> Unpin for With<Si, Item, U, Fut, F> {}
+impl<Si, Item> Unpin for Buffer<Si, Item> {}
+
+impl<Si, Item, U, Fut, F> With<Si, Item, U, Fut, F> {
+    pub(super) fn new(sink: Si, f: F) -> Self {
+        With {
+            sink: Box::pin(sink),
+            f,
+            pending: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<Si, Item, U, Fut, F, E> With<Si, Item, U, Fut, F>
+where
+    Si: Sink<Item>,
+    Fut: Future<Output = Result<Item, E>>,
+    E: From<Si::Error>,
+{
+    /// Finish turning the last value and send it on. The inner sink was
+    /// ready when the value was handed over.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let Some(pending) = &mut self.pending else {
+            return Poll::Ready(Ok(()));
+        };
+        let item = ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        self.sink.as_mut().start_send(item?)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<Si, Item, U, Fut, F, E> Sink<U> for With<Si, Item, U, Fut, F>
+where
+    Si: Sink<Item>,
+    F: FnMut(U) -> Fut,
+    Fut: Future<Output = Result<Item, E>>,
+    E: From<Si::Error>,
+{
+    type Error = E;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        ready!(this.sink.as_mut().poll_ready(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: U) -> Result<(), E> {
+        let this = self.get_mut();
+        this.pending = Some(Box::pin((this.f)(item)));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        ready!(this.sink.as_mut().poll_flush(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), E>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        ready!(this.sink.as_mut().poll_close(cx))?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<Si, Item> Buffer<Si, Item> {
+    pub(super) fn new(sink: Si, capacity: usize) -> Self {
+        Buffer {
+            sink: Box::pin(sink),
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+}
+
+impl<Si: Sink<Item>, Item> Buffer<Si, Item> {
+    /// Move as many buffered values into the sink as it takes
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        while !self.buffer.is_empty() {
+            ready!(self.sink.as_mut().poll_ready(cx))?;
+            let item = self.buffer.pop_front().unwrap();
+            self.sink.as_mut().start_send(item)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<Si: Sink<Item>, Item> Sink<Item> for Buffer<Si, Item> {
+    type Error = Si::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let this = self.get_mut();
+        if this.capacity == 0 {
+            return this.sink.as_mut().poll_ready(cx);
+        }
+        // A pending drain registered the waker, so a full buffer can wait
+        let _ = this.poll_drain(cx)?;
+        if this.buffer.len() >= this.capacity {
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Si::Error> {
+        let this = self.get_mut();
+        if this.capacity == 0 {
+            return this.sink.as_mut().start_send(item);
+        }
+        this.buffer.push_back(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        this.sink.as_mut().poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Si::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        this.sink.as_mut().poll_close(cx)
+    }
+}
+
+impl<A, B> Fanout<A, B> {
+    pub(super) fn new(a: A, b: B) -> Self {
+        Fanout {
+            a: Box::pin(a),
+            b: Box::pin(b),
+        }
+    }
+}
+
+impl<A, B, Item> Sink<Item> for Fanout<A, B>
+where
+    A: Sink<Item>,
+    B: Sink<Item, Error = A::Error>,
+    Item: Clone,
+{
+    type Error = A::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), A::Error>> {
+        let this = self.get_mut();
+        // Poll both, so both register the waker
+        let a = this.a.as_mut().poll_ready(cx)?;
+        let b = this.b.as_mut().poll_ready(cx)?;
+        if a.is_ready() && b.is_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), A::Error> {
+        let this = self.get_mut();
+        this.a.as_mut().start_send(item.clone())?;
+        this.b.as_mut().start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), A::Error>> {
+        let this = self.get_mut();
+        let a = this.a.as_mut().poll_flush(cx)?;
+        let b = this.b.as_mut().poll_flush(cx)?;
+        if a.is_ready() && b.is_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), A::Error>> {
+        let this = self.get_mut();
+        let a = this.a.as_mut().poll_close(cx)?;
+        let b = this.b.as_mut().poll_close(cx)?;
+        if a.is_ready() && b.is_ready() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,211 @@
+//! Asynchronous sequences of values: the `Stream` trait, the `StreamExt`
+//! adapters and ways to create streams.
+//!
+//! ```text
+//! let mut lines = runtime::async_stream!(y => {
+//!     for i in 0.. {
+//!         sleep(Duration::from_secs(1)).await;
+//!         y.yield_item(format!("line {i}")).await;
+//!     }
+//! })
+//! .take(3);
+//! while let Some(line) = lines.next().await {
+//!     println!("{line}");
+//! }
+//! ```
+//!
+//! The adapters box the stream they wrap, so they are `Unpin` and `next`
+//! can be called on them directly.
+mod adapters;
+mod buffer_unordered;
+mod generate;
+mod time;
+
+pub use adapters::{Chain, Filter, Map, Merge, Take, TakeWhile, Then, Zip};
+pub use buffer_unordered::BufferUnordered;
+pub use generate::{Generate, Iter, Yielder, generate, iter};
+pub use time::{ChunksTimeout, Throttle};
+
+use std::{
+    future::{Future, poll_fn},
+    ops::DerefMut,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The asynchronous version of `Iterator`
+pub trait Stream {
+    type Item;
+
+    /// `Ready(Some(item))` for the next item, `Ready(None)` once the stream
+    /// ended and `Pending` if the next item isn't there yet, the task is
+    /// woken once it is.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+}
+
+impl<S: Stream + Unpin + ?Sized> Stream for &mut S {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        Pin::new(&mut **self).poll_next(cx)
+    }
+}
+
+impl<S: Stream + Unpin + ?Sized> Stream for Box<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        Pin::new(&mut **self).poll_next(cx)
+    }
+}
+
+impl<P> Stream for Pin<P>
+where
+    P: DerefMut<Target: Stream>,
+{
+    type Item = <P::Target as Stream>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.as_deref_mut().poll_next(cx)
+    }
+}
+
+/// Adapters for every `Stream`
+pub trait StreamExt: Stream {
+    /// Wait for the next item
+    fn next(&mut self) -> impl Future<Output = Option<Self::Item>> + '_
+    where
+        Self: Unpin,
+    {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+    }
+
+    fn map<T, F>(self, f: F) -> Map<Self, F>
+    where
+        F: FnMut(Self::Item) -> T,
+        Self: Sized,
+    {
+        Map::new(self, f)
+    }
+
+    /// Only yield the items `predicate` returns `true` for
+    fn filter<F>(self, predicate: F) -> Filter<Self, F>
+    where
+        F: FnMut(&Self::Item) -> bool,
+        Self: Sized,
+    {
+        Filter::new(self, predicate)
+    }
+
+    /// Map every item with an async function, one item after the other
+    fn then<Fut, F>(self, f: F) -> Then<Self, F, Fut>
+    where
+        F: FnMut(Self::Item) -> Fut,
+        Fut: Future,
+        Self: Sized,
+    {
+        Then::new(self, f)
+    }
+
+    /// End after `n` items
+    fn take(self, n: usize) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, n)
+    }
+
+    /// End at the first item `predicate` returns `false` for
+    fn take_while<F>(self, predicate: F) -> TakeWhile<Self, F>
+    where
+        F: FnMut(&Self::Item) -> bool,
+        Self: Sized,
+    {
+        TakeWhile::new(self, predicate)
+    }
+
+    /// All items of this stream, then all items of `other`
+    fn chain<S>(self, other: S) -> Chain<Self, S>
+    where
+        S: Stream<Item = Self::Item>,
+        Self: Sized,
+    {
+        Chain::new(self, other)
+    }
+
+    /// Pairs of items of both streams, ends when one of them ends
+    fn zip<S>(self, other: S) -> Zip<Self, S>
+    where
+        S: Stream,
+        Self: Sized,
+    {
+        Zip::new(self, other)
+    }
+
+    /// The items of both streams as they arrive, ends when both ended
+    fn merge<S>(self, other: S) -> Merge<Self, S>
+    where
+        S: Stream<Item = Self::Item>,
+        Self: Sized,
+    {
+        Merge::new(self, other)
+    }
+
+    /// Combine all items into one value
+    fn fold<B, F>(self, init: B, mut f: F) -> impl Future<Output = B>
+    where
+        F: FnMut(B, Self::Item) -> B,
+        Self: Sized,
+    {
+        async move {
+            let mut stream = Box::pin(self);
+            let mut acc = init;
+            while let Some(item) = stream.next().await {
+                acc = f(acc, item);
+            }
+            acc
+        }
+    }
+
+    /// Collect all items, e.g. into a `Vec`
+    fn collect<C>(self) -> impl Future<Output = C>
+    where
+        C: Default + Extend<Self::Item>,
+        Self: Sized,
+    {
+        self.fold(C::default(), |mut collection, item| {
+            collection.extend(Some(item));
+            collection
+        })
+    }
+
+    /// For a stream of futures: run up to `limit` of them at the same time and
+    /// yield their outputs in the order they complete
+    fn buffer_unordered(self, limit: usize) -> BufferUnordered<Self>
+    where
+        Self::Item: Future,
+        Self: Sized,
+    {
+        BufferUnordered::new(self, limit)
+    }
+
+    /// Yield at most one item per `period`, items are delayed, not dropped
+    fn throttle(self, period: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle::new(self, period)
+    }
+
+    /// Group the items into chunks of up to `max` items. A chunk is yielded
+    /// once it's full or `timeout` after its first item arrived.
+    fn chunks_timeout(self, max: usize, timeout: Duration) -> ChunksTimeout<Self>
+    where
+        Self: Sized,
+    {
+        ChunksTimeout::new(self, max, timeout)
+    }
+}
+
+impl<S: Stream + ?Sized> StreamExt for S {}
@@ -0,0 +1,295 @@
+use super::Stream;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+pub struct Map<S, F> {
+    stream: Pin<Box<S>>,
+    f: F,
+}
+
+pub struct Filter<S, F> {
+    stream: Pin<Box<S>>,
+    predicate: F,
+}
+
+pub struct Then<S, F, Fut> {
+    stream: Pin<Box<S>>,
+    f: F,
+    // The future for the current item
+    pending: Option<Pin<Box<Fut>>>,
+}
+
+pub struct Take<S> {
+    stream: Pin<Box<S>>,
+    remaining: usize,
+}
+
+pub struct TakeWhile<S, F> {
+    stream: Pin<Box<S>>,
+    predicate: F,
+    done: bool,
+}
+
+pub struct Chain<A, B> {
+    first: Option<Pin<Box<A>>>,
+    second: Pin<Box<B>>,
+}
+
+pub struct Zip<A: Stream, B> {
+    a: Pin<Box<A>>,
+    b: Pin<Box<B>>,
+    // An item of `a` that waits for its partner
+    buffered: Option<A::Item>,
+}
+
+pub struct Merge<A, B> {
+    a: Option<Pin<Box<A>>>,
+    b: Option<Pin<Box<B>>>,
+    // Alternates, so a busy stream can't starve the other one
+    a_first: bool,
+}
+
+// Only the boxed streams are pinned, never the closures or buffered items
+impl<S, F> Unpin for Map<S, F> {}
+impl<S, F> Unpin for Filter<S, F> {}
+impl<S, F, Fut> Unpin for Then<S, F, Fut> {}
+impl<S, F> Unpin for TakeWhile<S, F> {}
+impl<A: Stream, B> Unpin for Zip<A, B> {}
+
+impl<S, F> Map<S, F> {
+    pub(super) fn new(stream: S, f: F) -> Self {
+        Map {
+            stream: Box::pin(stream),
+            f,
+        }
+    }
+}
+
+impl<S: Stream, F: FnMut(S::Item) -> T, T> Stream for Map<S, F> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let item = ready!(this.stream.as_mut().poll_next(cx));
+        Poll::Ready(item.map(&mut this.f))
+    }
+}
+
+impl<S, F> Filter<S, F> {
+    pub(super) fn new(stream: S, predicate: F) -> Self {
+        Filter {
+            stream: Box::pin(stream),
+            predicate,
+        }
+    }
+}
+
+impl<S: Stream, F: FnMut(&S::Item) -> bool> Stream for Filter<S, F> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        loop {
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(item) if !(this.predicate)(&item) => continue,
+                item => return Poll::Ready(item),
+            }
+        }
+    }
+}
+
+impl<S, F, Fut> Then<S, F, Fut> {
+    pub(super) fn new(stream: S, f: F) -> Self {
+        Then {
+            stream: Box::pin(stream),
+            f,
+            pending: None,
+        }
+    }
+}
+
+impl<S, F, Fut> Stream for Then<S, F, Fut>
+where
+    S: Stream,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future,
+{
+    type Item = Fut::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Fut::Output>> {
+        let this = self.get_mut();
+        if this.pending.is_none() {
+            let Some(item) = ready!(this.stream.as_mut().poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            this.pending = Some(Box::pin((this.f)(item)));
+        }
+        let output = ready!(this.pending.as_mut().unwrap().as_mut().poll(cx));
+        this.pending = None;
+        Poll::Ready(Some(output))
+    }
+}
+
+impl<S> Take<S> {
+    pub(super) fn new(stream: S, n: usize) -> Self {
+        Take {
+            stream: Box::pin(stream),
+            remaining: n,
+        }
+    }
+}
+
+impl<S: Stream> Stream for Take<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if this.remaining == 0 {
+            return Poll::Ready(None);
+        }
+        let item = ready!(this.stream.as_mut().poll_next(cx));
+        this.remaining = if item.is_some() {
+            this.remaining - 1
+        } else {
+            0
+        };
+        Poll::Ready(item)
+    }
+}
+
+impl<S, F> TakeWhile<S, F> {
+    pub(super) fn new(stream: S, predicate: F) -> Self {
+        TakeWhile {
+            stream: Box::pin(stream),
+            predicate,
+            done: false,
+        }
+    }
+}
+
+impl<S: Stream, F: FnMut(&S::Item) -> bool> Stream for TakeWhile<S, F> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        match ready!(this.stream.as_mut().poll_next(cx)) {
+            Some(item) if (this.predicate)(&item) => Poll::Ready(Some(item)),
+            _ => {
+                this.done = true;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+impl<A, B> Chain<A, B> {
+    pub(super) fn new(first: A, second: B) -> Self {
+        Chain {
+            first: Some(Box::pin(first)),
+            second: Box::pin(second),
+        }
+    }
+}
+
+impl<A: Stream, B: Stream<Item = A::Item>> Stream for Chain<A, B> {
+    type Item = A::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<A::Item>> {
+        let this = self.get_mut();
+        if let Some(first) = &mut this.first {
+            match ready!(first.as_mut().poll_next(cx)) {
+                Some(item) => return Poll::Ready(Some(item)),
+                None => this.first = None,
+            }
+        }
+        this.second.as_mut().poll_next(cx)
+    }
+}
+
+impl<A: Stream, B> Zip<A, B> {
+    pub(super) fn new(a: A, b: B) -> Self {
+        Zip {
+            a: Box::pin(a),
+            b: Box::pin(b),
+            buffered: None,
+        }
+    }
+}
+
+impl<A: Stream, B: Stream> Stream for Zip<A, B> {
+    type Item = (A::Item, B::Item);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.buffered.is_none() {
+            match ready!(this.a.as_mut().poll_next(cx)) {
+                Some(item) => this.buffered = Some(item),
+                None => return Poll::Ready(None),
+            }
+        }
+        match ready!(this.b.as_mut().poll_next(cx)) {
+            Some(item) => Poll::Ready(Some((this.buffered.take().unwrap(), item))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<A, B> Merge<A, B> {
+    pub(super) fn new(a: A, b: B) -> Self {
+        Merge {
+            a: Some(Box::pin(a)),
+            b: Some(Box::pin(b)),
+            a_first: true,
+        }
+    }
+}
+
+/// Poll a stream that might have ended already, forget it once it ends
+fn poll_side<S: Stream>(
+    side: &mut Option<Pin<Box<S>>>,
+    cx: &mut Context<'_>,
+) -> Poll<Option<S::Item>> {
+    let Some(stream) = side else {
+        return Poll::Ready(None);
+    };
+    let item = ready!(stream.as_mut().poll_next(cx));
+    if item.is_none() {
+        *side = None;
+    }
+    Poll::Ready(item)
+}
+
+impl<A: Stream, B: Stream<Item = A::Item>> Stream for Merge<A, B> {
+    type Item = A::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<A::Item>> {
+        let this = self.get_mut();
+        this.a_first = !this.a_first;
+        if this.a_first {
+            if let Poll::Ready(Some(item)) = poll_side(&mut this.a, cx) {
+                return Poll::Ready(Some(item));
+            }
+            if let Poll::Ready(Some(item)) = poll_side(&mut this.b, cx) {
+                return Poll::Ready(Some(item));
+            }
+        } else {
+            if let Poll::Ready(Some(item)) = poll_side(&mut this.b, cx) {
+                return Poll::Ready(Some(item));
+            }
+            if let Poll::Ready(Some(item)) = poll_side(&mut this.a, cx) {
+                return Poll::Ready(Some(item));
+            }
+        }
+        if this.a.is_none() && this.b.is_none() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::Stream;
+use crate::future::FuturesUnordered;
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Stream returned by `StreamExt::buffer_unordered`
+pub struct BufferUnordered<S: Stream>
+where
+    S::Item: Future,
+{
+    stream: Pin<Box<S>>,
+    in_flight: FuturesUnordered<S::Item>,
+    limit: usize,
+    stream_done: bool,
+}
+
+impl<S: Stream> BufferUnordered<S>
+where
+    S::Item: Future,
+{
+    pub(super) fn new(stream: S, limit: usize) -> Self {
+        assert!(limit > 0, "buffer_unordered limit must be at least 1");
+        BufferUnordered {
+            stream: Box::pin(stream),
+            in_flight: FuturesUnordered::new(),
+            limit,
+            stream_done: false,
+        }
+    }
+}
+
+impl<S: Stream> Stream for BufferUnordered<S>
+where
+    S::Item: Future,
+{
+    type Item = <S::Item as Future>::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.stream_done && this.in_flight.len() < this.limit {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(future)) => this.in_flight.push(future),
+                Poll::Ready(None) => this.stream_done = true,
+                Poll::Pending => break,
+            }
+        }
+        match this.in_flight.poll_next(cx) {
+            Poll::Ready(Some(output)) => Poll::Ready(Some(output)),
+            // Nothing in flight, but the stream might still produce futures
+            Poll::Ready(None) if !this.stream_done => Poll::Pending,
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::Stream;
+use std::{
+    future::{Future, poll_fn},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+/// Stream returned by `iter`
+pub struct Iter<I> {
+    iter: I,
+}
+
+/// Turn an iterator into a stream that is always ready
+pub fn iter<I: IntoIterator>(iter: I) -> Iter<I::IntoIter> {
+    Iter {
+        iter: iter.into_iter(),
+    }
+}
+
+// The iterator is never pinned
+impl<I> Unpin for Iter<I> {}
+
+impl<I: Iterator> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.get_mut().iter.next())
+    }
+}
+
+/// Stream returned by `generate` and `async_stream!`
+pub struct Generate<T, Fut> {
+    future: Option<Pin<Box<Fut>>>,
+    slot: Arc<Mutex<Option<T>>>,
+}
+
+/// Hands the items of a `generate` stream to its consumer
+pub struct Yielder<T> {
+    slot: Arc<Mutex<Option<T>>>,
+}
+
+/// Build a stream from an async block. The block gets a `Yielder` and
+/// every `yielder.yield_item(item).await` produces the next item, the
+/// stream ends when the block returns. Usually written with `async_stream!`.
+pub fn generate<T, F, Fut>(f: F) -> Generate<T, Fut>
+where
+    F: FnOnce(Yielder<T>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let slot = Arc::new(Mutex::new(None));
+    let future = f(Yielder { slot: slot.clone() });
+    Generate {
+        future: Some(Box::pin(future)),
+        slot,
+    }
+}
+
+impl<T> Yielder<T> {
+    /// Hand `item` to the consumer and continue once it asks for the next one
+    pub fn yield_item(&self, item: T) -> impl Future<Output = ()> + '_ {
+        let mut item = Some(item);
+        poll_fn(move |_cx| match item.take() {
+            Some(item) => {
+                *self.slot.lock().unwrap() = Some(item);
+                // No wake-up needed, the stream returns the item instead
+                // and the block continues on the next `poll_next`
+                Poll::Pending
+            }
+            None => Poll::Ready(()),
+        })
+    }
+}
+
+impl<T, Fut: Future<Output = ()>> Stream for Generate<T, Fut> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let Some(future) = &mut this.future else {
+            return Poll::Ready(None);
+        };
+        let done = future.as_mut().poll(cx).is_ready();
+        if done {
+            this.future = None;
+        }
+        match this.slot.lock().unwrap().take() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if done => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use super::Stream;
+use crate::time::{Sleep, sleep};
+use std::{
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+
+/// Stream returned by `StreamExt::throttle`
+pub struct Throttle<S> {
+    stream: Pin<Box<S>>,
+    period: Duration,
+    // Set after an item was yielded, the next one waits for it
+    sleep: Option<Sleep>,
+}
+
+/// Stream returned by `StreamExt::chunks_timeout`
+pub struct ChunksTimeout<S: Stream> {
+    stream: Pin<Box<S>>,
+    chunk: Vec<S::Item>,
+    max: usize,
+    timeout: Duration,
+    // Started with the first item of a chunk
+    deadline: Option<Sleep>,
+    done: bool,
+}
+
+// The buffered items are never pinned
+impl<S: Stream> Unpin for ChunksTimeout<S> {}
+
+impl<S> Throttle<S> {
+    pub(super) fn new(stream: S, period: Duration) -> Self {
+        Throttle {
+            stream: Box::pin(stream),
+            period,
+            sleep: None,
+        }
+    }
+}
+
+impl<S: Stream> Stream for Throttle<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if let Some(sleep) = &mut this.sleep {
+            ready!(Pin::new(sleep).poll(cx));
+            this.sleep = None;
+        }
+        let item = ready!(this.stream.as_mut().poll_next(cx));
+        if item.is_some() {
+            this.sleep = Some(sleep(this.period));
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<S: Stream> ChunksTimeout<S> {
+    pub(super) fn new(stream: S, max: usize, timeout: Duration) -> Self {
+        assert!(max > 0, "chunks_timeout max must be at least 1");
+        ChunksTimeout {
+            stream: Box::pin(stream),
+            chunk: Vec::with_capacity(max),
+            max,
+            timeout,
+            deadline: None,
+            done: false,
+        }
+    }
+
+    fn take_chunk(&mut self) -> Vec<S::Item> {
+        self.deadline = None;
+        mem::replace(&mut self.chunk, Vec::with_capacity(self.max))
+    }
+}
+
+impl<S: Stream> Stream for ChunksTimeout<S> {
+    type Item = Vec<S::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Vec<S::Item>>> {
+        let this = self.get_mut();
+        while !this.done {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    if this.chunk.is_empty() {
+                        this.deadline = Some(sleep(this.timeout));
+                    }
+                    this.chunk.push(item);
+                    if this.chunk.len() == this.max {
+                        return Poll::Ready(Some(this.take_chunk()));
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => {
+                    let Some(deadline) = &mut this.deadline else {
+                        return Poll::Pending;
+                    };
+                    ready!(Pin::new(deadline).poll(cx));
+                    return Poll::Ready(Some(this.take_chunk()));
+                }
+            }
+        }
+        if this.chunk.is_empty() {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(this.take_chunk()))
+        }
+    }
+}
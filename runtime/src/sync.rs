@@ -0,0 +1,31 @@
+//! Synchronization primitives for tasks. Waiting on them yields to the
+//! executor and the task is woken once it can continue, the thread is never
+//! blocked.
+mod barrier;
+mod batch_semaphore;
+pub mod broadcast;
+mod condvar;
+mod event;
+mod latch;
+pub mod mpsc;
+mod mutex;
+mod notify;
+pub mod oneshot;
+mod rwlock;
+mod semaphore;
+mod wait_group;
+pub mod watch;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use condvar::Condvar;
+pub use event::Event;
+pub use latch::CountDownLatch;
+pub use mutex::{Mutex, MutexGuard, OwnedMutexGuard};
+pub use notify::{Notified, Notify};
+pub use rwlock::{
+    OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+pub use semaphore::{
+    AcquireError, OwnedSemaphorePermit, Semaphore, SemaphorePermit, TryAcquireError,
+};
+pub use wait_group::WaitGroup;
@@ -0,0 +1,67 @@
+use super::Notify;
+use std::sync::Mutex;
+
+/// Lets a fixed number of tasks wait for each other before any of them
+/// continues. The barrier can be reused: once all tasks arrived it starts
+/// over for the next phase.
+#[derive(Debug)]
+pub struct Barrier {
+    n: usize,
+    state: Mutex<BarrierState>,
+    notify: Notify,
+}
+
+#[derive(Debug)]
+struct BarrierState {
+    arrived: usize,
+    // Incremented every time the barrier opens
+    generation: u64,
+}
+
+/// Returned by `Barrier::wait`, exactly one task per phase is the leader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// A barrier for `n` tasks, `0` is treated like `1`
+    pub fn new(n: usize) -> Self {
+        Barrier {
+            n: n.max(1),
+            state: Mutex::new(BarrierState {
+                arrived: 0,
+                generation: 0,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Wait until `n` tasks called `wait`. The last one to arrive opens the
+    /// barrier and is the leader.
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let generation = {
+            let mut state = self.state.lock().unwrap();
+            state.arrived += 1;
+            if state.arrived == self.n {
+                state.arrived = 0;
+                state.generation += 1;
+                drop(state);
+                self.notify.notify_waiters();
+                return BarrierWaitResult(true);
+            }
+            state.generation
+        };
+        loop {
+            let notified = self.notify.notified();
+            if self.state.lock().unwrap().generation != generation {
+                return BarrierWaitResult(false);
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::coop;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+/// Most permits a semaphore can hold, leaves headroom so counting them never overflows
+pub(crate) const MAX_PERMITS: usize = usize::MAX >> 3;
+
+/// FIFO semaphore the locks and the public `Semaphore` are built on. Waiters
+/// are served strictly in the order they arrived: a waiter that needs more
+/// permits than are available blocks everyone behind it, so big acquisitions
+/// (e.g. a writer of a `RwLock`) can't be starved by a stream of small ones.
+pub(crate) struct Semaphore {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Closed;
+
+struct Waiter {
+    needed: usize,
+    waker: Option<Waker>,
+    outcome: Option<Result<(), Closed>>,
+}
+
+struct State {
+    permits: usize,
+    // Permits the semaphore owns, whether handed out or not
+    total: usize,
+    closed: bool,
+    next_id: u64,
+    queue: VecDeque<u64>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+impl State {
+    fn give_back(&mut self, permits: usize) {
+        self.permits = self
+            .permits
+            .checked_add(permits)
+            .filter(|available| *available <= self.total)
+            .expect("released more permits than the semaphore holds");
+    }
+
+    /// Hand out permits to the waiters at the front of the queue
+    fn assign(&mut self, wakers: &mut Vec<Waker>) {
+        while let Some(id) = self.queue.front() {
+            let waiter = self.waiters.get_mut(id).unwrap();
+            if waiter.needed > self.permits {
+                break;
+            }
+            self.permits -= waiter.needed;
+            waiter.outcome = Some(Ok(()));
+            wakers.extend(waiter.waker.take());
+            self.queue.pop_front();
+        }
+    }
+}
+
+impl Semaphore {
+    pub(crate) fn new(permits: usize) -> Self {
+        assert!(
+            permits <= MAX_PERMITS,
+            "a semaphore can't hold more than {MAX_PERMITS} permits"
+        );
+        Semaphore {
+            state: Mutex::new(State {
+                permits,
+                total: permits,
+                closed: false,
+                next_id: 0,
+                queue: VecDeque::new(),
+                waiters: HashMap::new(),
+            }),
+        }
+    }
+
+    pub(crate) fn acquire(&self, permits: usize) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            needed: permits,
+            id: None,
+        }
+    }
+
+    /// Take the permits right away, this never jumps the queue
+    pub(crate) fn try_acquire(&self, permits: usize) -> Result<bool, Closed> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(Closed);
+        }
+        if state.queue.is_empty() && state.permits >= permits {
+            state.permits -= permits;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn available_permits(&self) -> usize {
+        self.state.lock().unwrap().permits
+    }
+
+    /// Fail all pending and future acquisitions
+    pub(crate) fn close(&self) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.closed = true;
+            let State { queue, waiters, .. } = &mut *state;
+            for id in queue.drain(..) {
+                let waiter = waiters.get_mut(&id).unwrap();
+                waiter.outcome = Some(Err(Closed));
+                wakers.extend(waiter.waker.take());
+            }
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    /// `Acquire` as a poll function for owners that can't hold a borrowing
+    /// future. `id` tracks the queued waiter between polls, if the caller
+    /// stops polling while it is set it must `cancel` it.
+    ///
+    /// A waiter may need more permits than the semaphore holds right now,
+    /// it is served once `add_permits` added enough.
+    pub(crate) fn poll_acquire(
+        &self,
+        cx: &mut Context<'_>,
+        needed: usize,
+        id: &mut Option<u64>,
+    ) -> Poll<Result<(), Closed>> {
+        coop::poll_budgeted(cx, |cx| {
+            let mut state = self.state.lock().unwrap();
+            let Some(queued) = *id else {
+                if state.closed {
+                    return Poll::Ready(Err(Closed));
+                }
+                if state.queue.is_empty() && state.permits >= needed {
+                    state.permits -= needed;
+                    return Poll::Ready(Ok(()));
+                }
+                let queued = state.next_id;
+                state.next_id += 1;
+                state.queue.push_back(queued);
+                state.waiters.insert(
+                    queued,
+                    Waiter {
+                        needed,
+                        waker: Some(cx.waker().clone()),
+                        outcome: None,
+                    },
+                );
+                *id = Some(queued);
+                return Poll::Pending;
+            };
+            let waiter = state.waiters.get_mut(&queued).unwrap();
+            if let Some(outcome) = waiter.outcome {
+                state.waiters.remove(&queued);
+                *id = None;
+                return Poll::Ready(outcome);
+            }
+            match &waiter.waker {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => waiter.waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+    }
+
+    /// Give up a waiter queued by `poll_acquire`
+    pub(crate) fn cancel(&self, id: u64, needed: usize) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            let waiter = state.waiters.remove(&id).unwrap();
+            match waiter.outcome {
+                // The permits were assigned, but never picked up
+                Some(Ok(())) => state.give_back(needed),
+                Some(Err(Closed)) => (),
+                None => state.queue.retain(|queued| *queued != id),
+            }
+            // Removing a waiter might unblock the ones behind it
+            state.assign(&mut wakers);
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Give back permits that were handed out
+    pub(crate) fn release(&self, permits: usize) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.give_back(permits);
+            state.assign(&mut wakers);
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Grow the semaphore by new permits
+    pub(crate) fn add_permits(&self, permits: usize) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.total = state
+                .total
+                .checked_add(permits)
+                .filter(|total| *total <= MAX_PERMITS)
+                .unwrap_or_else(|| {
+                    panic!("a semaphore can't hold more than {MAX_PERMITS} permits")
+                });
+            state.give_back(permits);
+            state.assign(&mut wakers);
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Shrink the semaphore by permits that were handed out and won't come back
+    pub(crate) fn forget(&self, permits: usize) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.total = state.total.saturating_sub(permits);
+            state.permits = state.permits.min(state.total);
+            state.assign(&mut wakers);
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+pub(crate) struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    needed: usize,
+    // Set while queued
+    id: Option<u64>,
+}
+
+impl Future for Acquire<'_> {
+    type Output = Result<(), Closed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Acquire {
+            semaphore,
+            needed,
+            id,
+        } = &mut *self;
+        semaphore.poll_acquire(cx, *needed, id)
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.semaphore.cancel(id, self.needed);
+        }
+    }
+}
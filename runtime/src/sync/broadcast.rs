@@ -0,0 +1,262 @@
+//! Multi-producer, multi-consumer channel where every receiver sees every
+//! value.
+//!
+//! The last `capacity` values are kept in a ring buffer and every receiver
+//! has its own cursor into it. A receiver that falls behind by more than
+//! `capacity` values misses the oldest ones: its next `recv` returns
+//! `RecvError::Lagged` with the number of skipped values and it continues
+//! with the oldest value that is still buffered.
+use crate::coop;
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    future::poll_fn,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Create a channel that buffers `capacity` values, panics if it is `0`
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(
+        capacity > 0,
+        "broadcast channel capacity must be at least 1"
+    );
+    let shared = Arc::new(Mutex::new(State {
+        buffer: VecDeque::with_capacity(capacity),
+        capacity,
+        head: 0,
+        senders: 1,
+        receivers: 0,
+        next_id: 0,
+        waiters: HashMap::new(),
+    }));
+    let receiver = Receiver::new(shared.clone(), 0);
+    (Sender { shared }, receiver)
+}
+
+struct State<T> {
+    buffer: VecDeque<T>,
+    capacity: usize,
+    // Position of the oldest buffered value, the newest one is `head + buffer.len() - 1`
+    head: u64,
+    senders: usize,
+    receivers: usize,
+    next_id: u64,
+    // Wakers of the receivers waiting in `recv`, by receiver id
+    waiters: HashMap<u64, Waker>,
+}
+
+impl<T> State<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Mutex<State<T>>>,
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Mutex<State<T>>>,
+    id: u64,
+    // Position of the next value to receive
+    next: u64,
+}
+
+/// There is no receiver, the value is handed back
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecvError {
+    /// All senders are gone and every buffered value was received
+    Closed,
+    /// The receiver fell behind, this many values were skipped
+    Lagged(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Closed,
+    Lagged(u64),
+}
+
+impl<T> Sender<T> {
+    /// Send the value to all receivers, returns how many there are.
+    /// Never waits, if the buffer is full the oldest value is overwritten.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let (receivers, wakers) = {
+            let mut state = self.shared.lock().unwrap();
+            if state.receivers == 0 {
+                return Err(SendError(value));
+            }
+            if state.buffer.len() == state.capacity {
+                state.buffer.pop_front();
+                state.head += 1;
+            }
+            state.buffer.push_back(value);
+            let wakers: Vec<_> = state.waiters.drain().map(|(_, waker)| waker).collect();
+            (state.receivers, wakers)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+        Ok(receivers)
+    }
+
+    /// A new receiver that gets all values sent from now on
+    pub fn subscribe(&self) -> Receiver<T> {
+        let tail = self.shared.lock().unwrap().tail();
+        Receiver::new(self.shared.clone(), tail)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.shared.lock().unwrap().receivers
+    }
+}
+
+impl<T> Receiver<T> {
+    fn new(shared: Arc<Mutex<State<T>>>, next: u64) -> Self {
+        let id = {
+            let mut state = shared.lock().unwrap();
+            state.receivers += 1;
+            state.next_id += 1;
+            state.next_id
+        };
+        Receiver { shared, id, next }
+    }
+
+    /// A new receiver that gets all values sent from now on
+    pub fn resubscribe(&self) -> Self {
+        let tail = self.shared.lock().unwrap().tail();
+        Receiver::new(self.shared.clone(), tail)
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.next_value(None) {
+            Poll::Ready(Ok(value)) => Ok(value),
+            Poll::Ready(Err(RecvError::Closed)) => Err(TryRecvError::Closed),
+            Poll::Ready(Err(RecvError::Lagged(n))) => Err(TryRecvError::Lagged(n)),
+            Poll::Pending => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+        coop::poll_budgeted(cx, |cx| self.next_value(Some(cx.waker())))
+    }
+
+    fn next_value(&mut self, waker: Option<&Waker>) -> Poll<Result<T, RecvError>> {
+        let mut state = self.shared.lock().unwrap();
+        if self.next < state.head {
+            let skipped = state.head - self.next;
+            self.next = state.head;
+            return Poll::Ready(Err(RecvError::Lagged(skipped)));
+        }
+        if self.next < state.tail() {
+            let value = state.buffer[(self.next - state.head) as usize].clone();
+            self.next += 1;
+            return Poll::Ready(Ok(value));
+        }
+        if state.senders == 0 {
+            return Poll::Ready(Err(RecvError::Closed));
+        }
+        if let Some(waker) = waker {
+            match state.waiters.get(&self.id) {
+                Some(current) if current.will_wake(waker) => (),
+                _ => {
+                    state.waiters.insert(self.id, waker.clone());
+                }
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().unwrap().senders += 1;
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers: Vec<_> = {
+            let mut state = self.shared.lock().unwrap();
+            state.senders -= 1;
+            if state.senders > 0 {
+                return;
+            }
+            state.waiters.drain().map(|(_, waker)| waker).collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock().unwrap();
+        state.receivers -= 1;
+        // The receiver might be dropped while a `recv` waits
+        state.waiters.remove(&self.id);
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no receivers")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Closed => write!(f, "channel closed"),
+            RecvError::Lagged(n) => write!(f, "receiver lagged behind by {n} values"),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Closed => write!(f, "channel closed"),
+            TryRecvError::Lagged(n) => write!(f, "receiver lagged behind by {n} values"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
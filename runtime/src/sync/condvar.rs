@@ -0,0 +1,48 @@
+use super::{MutexGuard, Notify};
+
+/// Condition variable for the async `Mutex`: `wait` unlocks the mutex,
+/// waits for a notification and locks it again.
+///
+/// As with `std::sync::Condvar` a task may wake up without the condition
+/// being true, so check it in a loop or use `wait_while`.
+#[derive(Debug, Default)]
+pub struct Condvar {
+    notify: Notify,
+}
+
+impl Condvar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = MutexGuard::mutex(&guard);
+        let mut notified = self.notify.notified();
+        // Queue up while still holding the lock, so no notification is lost
+        notified.enable();
+        drop(guard);
+        notified.await;
+        mutex.lock().await
+    }
+
+    /// Wait as long as `condition` returns `true`
+    pub async fn wait_while<'a, T: ?Sized>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: impl FnMut(&mut T) -> bool,
+    ) -> MutexGuard<'a, T> {
+        while condition(&mut *guard) {
+            guard = self.wait(guard).await;
+        }
+        guard
+    }
+
+    /// Wake one waiting task, nothing happens if there is none
+    pub fn notify_one(&self) {
+        self.notify.notify_one_waiter();
+    }
+
+    pub fn notify_all(&self) {
+        self.notify.notify_waiters();
+    }
+}
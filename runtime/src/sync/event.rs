@@ -0,0 +1,66 @@
+use super::Notify;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag that tasks can wait for.
+///
+/// A manual-reset event stays set and lets every waiter through until
+/// `reset` is called. An auto-reset event lets exactly one waiter through per
+/// `set` and resets itself again, setting it while it is set does nothing.
+#[derive(Debug)]
+pub struct Event {
+    set: AtomicBool,
+    auto_reset: bool,
+    notify: Notify,
+}
+
+impl Event {
+    pub fn manual_reset() -> Self {
+        Event {
+            set: AtomicBool::new(false),
+            auto_reset: false,
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn auto_reset() -> Self {
+        Event {
+            set: AtomicBool::new(false),
+            auto_reset: true,
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn set(&self) {
+        let was_set = self.set.swap(true, Ordering::AcqRel);
+        if !self.auto_reset {
+            self.notify.notify_waiters();
+        } else if !was_set {
+            self.notify.notify_one();
+        }
+    }
+
+    pub fn reset(&self) {
+        self.set.store(false, Ordering::Release);
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.set.load(Ordering::Acquire)
+    }
+
+    /// Wait until the event is set, an auto-reset event is reset again
+    pub async fn wait(&self) {
+        loop {
+            // Created before the check, so a `set` in between isn't missed
+            let notified = self.notify.notified();
+            let passed = if self.auto_reset {
+                self.set.swap(false, Ordering::AcqRel)
+            } else {
+                self.is_set()
+            };
+            if passed {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
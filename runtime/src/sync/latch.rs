@@ -0,0 +1,46 @@
+use super::Notify;
+use std::sync::Mutex;
+
+/// Opens once `count_down` was called `count` times and stays open for good
+#[derive(Debug)]
+pub struct CountDownLatch {
+    count: Mutex<usize>,
+    notify: Notify,
+}
+
+impl CountDownLatch {
+    pub fn new(count: usize) -> Self {
+        CountDownLatch {
+            count: Mutex::new(count),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Does nothing once the latch is open
+    pub fn count_down(&self) {
+        let mut count = self.count.lock().unwrap();
+        if *count == 0 {
+            return;
+        }
+        *count -= 1;
+        if *count == 0 {
+            drop(count);
+            self.notify.notify_waiters();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        *self.count.lock().unwrap()
+    }
+
+    /// Wait until the count reached zero
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.count() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Multi-producer, single-consumer queue for sending values between tasks.
+//!
+//! The bounded `channel` holds at most `capacity` values, `send` waits for a
+//! free slot and waiting senders are served in FIFO order. The
+//! `unbounded_channel` never waits. `recv` returns `None` once all senders
+//! are dropped and the queue is drained.
+//!
+//! Both senders are also a `Sink`, for a bounded one `poll_ready` reserves
+//! the slot the next value goes into.
+use super::batch_semaphore::Semaphore;
+use crate::{coop, sink::Sink, stream::Stream};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    future::poll_fn,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Create a channel that holds at most `capacity` values, panics if it is `0`
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "mpsc channel capacity must be at least 1");
+    let chan = Chan::new(Some(Semaphore::new(capacity)));
+    (Sender::new(chan.clone()), Receiver { chan })
+}
+
+pub fn unbounded_channel<T>() -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    let chan = Chan::new(None);
+    (
+        UnboundedSender::new(chan.clone()),
+        UnboundedReceiver {
+            inner: Receiver { chan },
+        },
+    )
+}
+
+struct Chan<T> {
+    state: Mutex<State<T>>,
+    // Free slots of a bounded channel
+    slots: Option<Semaphore>,
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    // The receiver was closed or dropped
+    closed: bool,
+    rx_waker: Option<Waker>,
+}
+
+impl<T> Chan<T> {
+    fn new(slots: Option<Semaphore>) -> Arc<Self> {
+        Arc::new(Chan {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                senders: 1,
+                closed: false,
+                rx_waker: None,
+            }),
+            slots,
+        })
+    }
+
+    /// Queue a value, a bounded channel must have reserved a slot for it
+    fn push(&self, value: T) -> Result<(), T> {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            if state.closed {
+                return Err(value);
+            }
+            state.queue.push_back(value);
+            state.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+
+    fn add_sender(&self) {
+        self.state.lock().unwrap().senders += 1;
+    }
+
+    fn drop_sender(&self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.senders -= 1;
+            if state.senders > 0 {
+                return;
+            }
+            state.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+pub struct Sender<T> {
+    chan: Arc<Chan<T>>,
+    // Waits for a slot in `poll_ready`
+    waiter: Option<u64>,
+    // `poll_ready` got a slot, `start_send` uses it
+    reserved: bool,
+    // Closed as a sink, this sender no longer keeps the channel open
+    sink_closed: bool,
+}
+
+pub struct Receiver<T> {
+    chan: Arc<Chan<T>>,
+}
+
+pub struct UnboundedSender<T> {
+    chan: Arc<Chan<T>>,
+    sink_closed: bool,
+}
+
+pub struct UnboundedReceiver<T> {
+    inner: Receiver<T>,
+}
+
+/// The receiver is gone, the value is handed back
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel has no free slot right now
+    Full(T),
+    /// The receiver is gone
+    Closed(T),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No value queued, but there are still senders
+    Empty,
+    /// No value queued and all senders are gone
+    Disconnected,
+}
+
+impl<T> Sender<T> {
+    fn new(chan: Arc<Chan<T>>) -> Self {
+        Sender {
+            chan,
+            waiter: None,
+            reserved: false,
+            sink_closed: false,
+        }
+    }
+
+    /// Wait for a free slot and queue the value. Fails if the receiver is
+    /// closed or dropped.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let slots = self.chan.slots.as_ref().unwrap();
+        if slots.acquire(1).await.is_err() {
+            return Err(SendError(value));
+        }
+        self.chan.push(value).map_err(SendError)
+    }
+
+    /// Queue the value if there is a free slot and no other sender waits for one
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let slots = self.chan.slots.as_ref().unwrap();
+        match slots.try_acquire(1) {
+            Ok(true) => self.chan.push(value).map_err(TrySendError::Closed),
+            Ok(false) => Err(TrySendError::Full(value)),
+            Err(_) => Err(TrySendError::Closed(value)),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+
+    /// Number of free slots
+    pub fn capacity(&self) -> usize {
+        self.chan.slots.as_ref().unwrap().available_permits()
+    }
+}
+
+impl<T> UnboundedSender<T> {
+    fn new(chan: Arc<Chan<T>>) -> Self {
+        UnboundedSender {
+            chan,
+            sink_closed: false,
+        }
+    }
+
+    /// Queue the value. Fails if the receiver is closed or dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.chan.push(value).map_err(SendError)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.chan.is_closed()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Wait for the next value, `None` once all senders are gone (or the
+    /// receiver was closed) and the queue is empty
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = self.chan.state.lock().unwrap();
+        match state.queue.pop_front() {
+            Some(value) => {
+                drop(state);
+                self.release_slot();
+                Ok(value)
+            }
+            None if state.senders == 0 || state.closed => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        coop::poll_budgeted(cx, |cx| {
+            let mut state = self.chan.state.lock().unwrap();
+            if let Some(value) = state.queue.pop_front() {
+                drop(state);
+                self.release_slot();
+                return Poll::Ready(Some(value));
+            }
+            if state.senders == 0 || state.closed {
+                return Poll::Ready(None);
+            }
+            match &state.rx_waker {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => state.rx_waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+    }
+
+    /// Stop accepting new values, the ones already queued can still be received
+    pub fn close(&mut self) {
+        self.chan.state.lock().unwrap().closed = true;
+        // Fails the senders that wait for a slot
+        if let Some(slots) = &self.chan.slots {
+            slots.close();
+        }
+    }
+
+    fn release_slot(&self) {
+        if let Some(slots) = &self.chan.slots {
+            slots.release(1);
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+        // Values nobody will receive are dropped right away, not when the
+        // last sender is gone
+        let queue = std::mem::take(&mut self.chan.state.lock().unwrap().queue);
+        drop(queue);
+    }
+}
+
+impl<T> UnboundedReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        self.inner.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.inner.try_recv()
+    }
+
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.poll_recv(cx)
+    }
+
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+}
+
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+impl<T> Stream for UnboundedReceiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+/// The value of a failed `start_send` is dropped
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<()>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        let this = self.get_mut();
+        if this.sink_closed {
+            return Poll::Ready(Err(SendError(())));
+        }
+        if this.reserved {
+            return Poll::Ready(Ok(()));
+        }
+        let slots = this.chan.slots.as_ref().unwrap();
+        match slots.poll_acquire(cx, 1, &mut this.waiter) {
+            Poll::Ready(Ok(())) => {
+                this.reserved = true;
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(_)) => Poll::Ready(Err(SendError(()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), SendError<()>> {
+        let this = self.get_mut();
+        assert!(this.reserved, "start_send without a successful poll_ready");
+        // The slot now belongs to the queued value
+        this.reserved = false;
+        this.chan.push(item).map_err(|_| SendError(()))
+    }
+
+    /// Queued values can be received right away, there is nothing to flush
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// The channel ends once all senders are dropped or closed
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        let this = self.get_mut();
+        if let Some(id) = this.waiter.take() {
+            this.chan.slots.as_ref().unwrap().cancel(id, 1);
+        }
+        if std::mem::take(&mut this.reserved) {
+            this.chan.slots.as_ref().unwrap().release(1);
+        }
+        if !std::mem::replace(&mut this.sink_closed, true) {
+            this.chan.drop_sender();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The value of a failed `start_send` is dropped
+impl<T> Sink<T> for UnboundedSender<T> {
+    type Error = SendError<()>;
+
+    fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        if self.sink_closed || self.chan.is_closed() {
+            return Poll::Ready(Err(SendError(())));
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), SendError<()>> {
+        self.chan.push(item).map_err(|_| SendError(()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), SendError<()>>> {
+        let this = self.get_mut();
+        if !std::mem::replace(&mut this.sink_closed, true) {
+            this.chan.drop_sender();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.add_sender();
+        Sender::new(self.chan.clone())
+    }
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        self.chan.add_sender();
+        UnboundedSender::new(self.chan.clone())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let slots = self.chan.slots.as_ref().unwrap();
+        if let Some(id) = self.waiter {
+            slots.cancel(id, 1);
+        }
+        if self.reserved {
+            slots.release(1);
+        }
+        if !self.sink_closed {
+            self.chan.drop_sender();
+        }
+    }
+}
+
+impl<T> Drop for UnboundedSender<T> {
+    fn drop(&mut self) {
+        if !self.sink_closed {
+            self.chan.drop_sender();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for UnboundedSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedSender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for UnboundedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnboundedReceiver").finish_non_exhaustive()
+    }
+}
+
+// The values are usually not `Debug`, so they are left out
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "channel closed")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "Full(..)"),
+            TrySendError::Closed(_) => write!(f, "Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "channel full"),
+            TrySendError::Closed(_) => write!(f, "channel closed"),
+        }
+    }
+}
+
+impl<T> Error for TrySendError<T> {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel empty"),
+            TryRecvError::Disconnected => write!(f, "channel empty and all senders dropped"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
@@ -0,0 +1,222 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Mutex,
+    task::{Context, Poll, Waker},
+};
+
+/// Wakes tasks that wait for something to happen, without carrying any data.
+///
+/// `notify_one` wakes the task that waits the longest. If nobody waits, a
+/// single permit is stored and the next `notified().await` completes right
+/// away, so a notification sent just before the wait isn't lost.
+/// `notify_waiters` wakes every task that waits at that moment and stores
+/// nothing.
+pub struct Notify {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Notification {
+    One,
+    All,
+}
+
+struct Waiter {
+    waker: Option<Waker>,
+    notification: Option<Notification>,
+}
+
+struct State {
+    permit: bool,
+    // Counts the `notify_waiters` calls, a `Notified` that was created before
+    // one of them completes even if it wasn't registered yet
+    generation: u64,
+    next_id: u64,
+    queue: VecDeque<u64>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+impl State {
+    /// Wake the first waiter in the queue, returns `false` if there is none
+    fn notify_first(&mut self, wakers: &mut Vec<Waker>) -> bool {
+        let Some(id) = self.queue.pop_front() else {
+            return false;
+        };
+        let waiter = self.waiters.get_mut(&id).unwrap();
+        waiter.notification = Some(Notification::One);
+        wakers.extend(waiter.waker.take());
+        true
+    }
+}
+
+impl Notify {
+    pub fn new() -> Self {
+        Notify {
+            state: Mutex::new(State {
+                permit: false,
+                generation: 0,
+                next_id: 0,
+                queue: VecDeque::new(),
+                waiters: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Wait for a notification
+    pub fn notified(&self) -> Notified<'_> {
+        Notified {
+            notify: self,
+            generation: self.state.lock().unwrap().generation,
+            id: None,
+            done: false,
+        }
+    }
+
+    /// Wake the task that waits the longest, or store a permit for the next one
+    pub fn notify_one(&self) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            if !state.notify_first(&mut wakers) {
+                state.permit = true;
+            }
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Like `notify_one`, but the notification is dropped if nobody waits
+    pub(super) fn notify_one_waiter(&self) {
+        let mut wakers = Vec::new();
+        self.state.lock().unwrap().notify_first(&mut wakers);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Wake all tasks that wait right now
+    pub fn notify_waiters(&self) {
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.generation = state.generation.wrapping_add(1);
+            let State { queue, waiters, .. } = &mut *state;
+            for id in queue.drain(..) {
+                let waiter = waiters.get_mut(&id).unwrap();
+                waiter.notification = Some(Notification::All);
+                wakers.extend(waiter.waker.take());
+            }
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Notify::new()
+    }
+}
+
+impl fmt::Debug for Notify {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("Notify")
+            .field("permit", &state.permit)
+            .field("waiters", &state.queue.len())
+            .finish()
+    }
+}
+
+/// Future returned by `Notify::notified`
+pub struct Notified<'a> {
+    notify: &'a Notify,
+    generation: u64,
+    // Set while queued
+    id: Option<u64>,
+    done: bool,
+}
+
+impl Notified<'_> {
+    /// Queue up as a waiter without being polled, so a `notify_one` from
+    /// here on is received by this future instead of stored as a permit.
+    pub fn enable(&mut self) {
+        let _ = self.poll_notified(None);
+    }
+
+    fn poll_notified(&mut self, waker: Option<&Waker>) -> Poll<()> {
+        if self.done {
+            return Poll::Ready(());
+        }
+        let mut state = self.notify.state.lock().unwrap();
+        let Some(id) = self.id else {
+            // A `notify_waiters` since `notified` completes us without using
+            // up the permit, that is left for the next waiter
+            if state.generation != self.generation {
+                self.done = true;
+                return Poll::Ready(());
+            }
+            if state.permit {
+                state.permit = false;
+                self.done = true;
+                return Poll::Ready(());
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            state.queue.push_back(id);
+            state.waiters.insert(
+                id,
+                Waiter {
+                    waker: waker.cloned(),
+                    notification: None,
+                },
+            );
+            self.id = Some(id);
+            return Poll::Pending;
+        };
+        let waiter = state.waiters.get_mut(&id).unwrap();
+        if waiter.notification.is_some() {
+            state.waiters.remove(&id);
+            self.id = None;
+            self.done = true;
+            return Poll::Ready(());
+        }
+        match (&waiter.waker, waker) {
+            (Some(current), Some(waker)) if current.will_wake(waker) => (),
+            (_, Some(waker)) => waiter.waker = Some(waker.clone()),
+            (_, None) => (),
+        }
+        Poll::Pending
+    }
+}
+
+impl Future for Notified<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.poll_notified(Some(cx.waker()))
+    }
+}
+
+impl Drop for Notified<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+        let mut wakers = Vec::new();
+        {
+            let mut state = self.notify.state.lock().unwrap();
+            let waiter = state.waiters.remove(&id).unwrap();
+            match waiter.notification {
+                // Pass a `notify_one` that was never picked up on to the next waiter
+                Some(Notification::One) => {
+                    if !state.notify_first(&mut wakers) {
+                        state.permit = true;
+                    }
+                }
+                Some(Notification::All) => (),
+                None => state.queue.retain(|queued| *queued != id),
+            }
+        }
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
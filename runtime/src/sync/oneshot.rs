@@ -0,0 +1,198 @@
+//! Channel for sending a single value from one task to another, e.g. the
+//! result of a spawned task.
+use crate::coop;
+use std::{
+    error::Error,
+    fmt,
+    future::{Future, poll_fn},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// Create a connected sender and receiver
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Mutex::new(State {
+        value: None,
+        sender_gone: false,
+        receiver_gone: false,
+        rx_waker: None,
+        tx_waker: None,
+    }));
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+struct State<T> {
+    value: Option<T>,
+    // The sender was used up or dropped
+    sender_gone: bool,
+    // The receiver was closed or dropped
+    receiver_gone: bool,
+    rx_waker: Option<Waker>,
+    // Set by `Sender::closed`
+    tx_waker: Option<Waker>,
+}
+
+pub struct Sender<T> {
+    inner: Arc<Mutex<State<T>>>,
+}
+
+/// Awaiting the receiver yields the value, or an error if the sender was
+/// dropped without sending one
+pub struct Receiver<T> {
+    inner: Arc<Mutex<State<T>>>,
+}
+
+/// The sender was dropped without sending a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecvError(());
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// No value yet, but the sender still exists
+    Empty,
+    /// The sender was dropped without sending a value
+    Closed,
+}
+
+impl<T> Sender<T> {
+    /// Send the value, returns it if the receiver is gone
+    pub fn send(self, value: T) -> Result<(), T> {
+        let waker = {
+            let mut state = self.inner.lock().unwrap();
+            if state.receiver_gone {
+                return Err(value);
+            }
+            state.value = Some(value);
+            state.sender_gone = true;
+            state.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Whether the receiver was closed or dropped
+    pub fn is_closed(&self) -> bool {
+        self.inner.lock().unwrap().receiver_gone
+    }
+
+    /// Wait until the receiver is closed or dropped, e.g. to stop computing
+    /// a value nobody is interested in anymore
+    pub async fn closed(&mut self) {
+        poll_fn(|cx| {
+            let mut state = self.inner.lock().unwrap();
+            if state.receiver_gone {
+                return Poll::Ready(());
+            }
+            state.tx_waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.inner.lock().unwrap();
+            if state.sender_gone {
+                return;
+            }
+            state.sender_gone = true;
+            state.rx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Take the value if it was sent already
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let mut state = self.inner.lock().unwrap();
+        match state.value.take() {
+            Some(value) => Ok(value),
+            None if state.sender_gone => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Refuse the value, `send` fails from now on. A value that was sent
+    /// before can still be received.
+    pub fn close(&mut self) {
+        let waker = {
+            let mut state = self.inner.lock().unwrap();
+            state.receiver_gone = true;
+            state.tx_waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        coop::poll_budgeted(cx, |cx| {
+            let mut state = self.inner.lock().unwrap();
+            if let Some(value) = state.value.take() {
+                return Poll::Ready(Ok(value));
+            }
+            if state.sender_gone {
+                return Poll::Ready(Err(RecvError(())));
+            }
+            match &state.rx_waker {
+                Some(waker) if waker.will_wake(cx.waker()) => (),
+                _ => state.rx_waker = Some(cx.waker().clone()),
+            }
+            Poll::Pending
+        })
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sender dropped without sending a value")
+    }
+}
+
+impl Error for RecvError {}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "no value sent yet"),
+            TryRecvError::Closed => write!(f, "sender dropped without sending a value"),
+        }
+    }
+}
+
+impl Error for TryRecvError {}
@@ -0,0 +1,238 @@
+use super::batch_semaphore;
+use std::{error::Error, fmt, sync::Arc};
+
+/// Counting semaphore to limit how many tasks do something at the same time,
+/// e.g. how many connections are open.
+///
+/// Permits are handed out in FIFO order: a task that waits for many permits
+/// is not overtaken by later tasks that only need a few, so it can't starve.
+pub struct Semaphore {
+    ll: batch_semaphore::Semaphore,
+}
+
+/// Permits that are given back to the semaphore on drop
+#[must_use]
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+/// Permits that keep the semaphore alive, so they can be moved into another task
+#[must_use]
+pub struct OwnedSemaphorePermit {
+    semaphore: Arc<Semaphore>,
+    permits: usize,
+}
+
+/// The semaphore was closed, or more permits were asked for than a
+/// semaphore can hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AcquireError(Reason);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reason {
+    Closed,
+    TooManyPermits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryAcquireError {
+    Closed,
+    NoPermits,
+}
+
+impl Semaphore {
+    /// Most permits a semaphore can hold
+    pub const MAX_PERMITS: usize = batch_semaphore::MAX_PERMITS;
+
+    /// Panics if `permits` is more than `MAX_PERMITS`
+    pub fn new(permits: usize) -> Self {
+        Semaphore {
+            ll: batch_semaphore::Semaphore::new(permits),
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.ll.available_permits()
+    }
+
+    /// Add new permits, waiting tasks are served first.
+    /// Panics if the semaphore would hold more than `MAX_PERMITS`.
+    pub fn add_permits(&self, permits: usize) {
+        self.ll.add_permits(permits);
+    }
+
+    /// Fail all pending and future acquisitions.
+    /// Permits that were already handed out stay valid.
+    pub fn close(&self) {
+        self.ll.close();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.ll.is_closed()
+    }
+
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, AcquireError> {
+        self.acquire_many(1).await
+    }
+
+    /// Wait until `permits` permits are available and take all of them at once.
+    ///
+    /// If the semaphore holds fewer permits, this waits for `add_permits`.
+    /// Fails if `permits` is more than `MAX_PERMITS`, those could never be
+    /// acquired.
+    pub async fn acquire_many(&self, permits: usize) -> Result<SemaphorePermit<'_>, AcquireError> {
+        self.acquire_ll(permits).await?;
+        Ok(SemaphorePermit {
+            semaphore: self,
+            permits,
+        })
+    }
+
+    async fn acquire_ll(&self, permits: usize) -> Result<(), AcquireError> {
+        if permits > Self::MAX_PERMITS {
+            return Err(AcquireError(Reason::TooManyPermits));
+        }
+        self.ll
+            .acquire(permits)
+            .await
+            .map_err(|_| AcquireError(Reason::Closed))
+    }
+
+    pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        self.try_acquire_many(1)
+    }
+
+    /// Take the permits if they are available and nobody is waiting in front of us
+    pub fn try_acquire_many(&self, permits: usize) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        match self.ll.try_acquire(permits) {
+            Ok(true) => Ok(SemaphorePermit {
+                semaphore: self,
+                permits,
+            }),
+            Ok(false) => Err(TryAcquireError::NoPermits),
+            Err(_) => Err(TryAcquireError::Closed),
+        }
+    }
+
+    pub async fn acquire_owned(self: Arc<Self>) -> Result<OwnedSemaphorePermit, AcquireError> {
+        self.acquire_many_owned(1).await
+    }
+
+    /// Like `acquire_many`, fails if `permits` is more than `MAX_PERMITS`
+    pub async fn acquire_many_owned(
+        self: Arc<Self>,
+        permits: usize,
+    ) -> Result<OwnedSemaphorePermit, AcquireError> {
+        self.acquire_ll(permits).await?;
+        Ok(OwnedSemaphorePermit {
+            semaphore: self,
+            permits,
+        })
+    }
+
+    pub fn try_acquire_owned(self: Arc<Self>) -> Result<OwnedSemaphorePermit, TryAcquireError> {
+        self.try_acquire_many_owned(1)
+    }
+
+    pub fn try_acquire_many_owned(
+        self: Arc<Self>,
+        permits: usize,
+    ) -> Result<OwnedSemaphorePermit, TryAcquireError> {
+        match self.ll.try_acquire(permits) {
+            Ok(true) => Ok(OwnedSemaphorePermit {
+                semaphore: self,
+                permits,
+            }),
+            Ok(false) => Err(TryAcquireError::NoPermits),
+            Err(_) => Err(TryAcquireError::Closed),
+        }
+    }
+}
+
+impl fmt::Debug for Semaphore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Semaphore")
+            .field("permits", &self.available_permits())
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl SemaphorePermit<'_> {
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Drop the permit without giving it back to the semaphore
+    pub fn forget(mut self) {
+        self.semaphore.ll.forget(self.permits);
+        self.permits = 0;
+    }
+}
+
+impl OwnedSemaphorePermit {
+    pub fn num_permits(&self) -> usize {
+        self.permits
+    }
+
+    pub fn semaphore(&self) -> &Arc<Semaphore> {
+        &self.semaphore
+    }
+
+    /// Drop the permit without giving it back to the semaphore
+    pub fn forget(mut self) {
+        self.semaphore.ll.forget(self.permits);
+        self.permits = 0;
+    }
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.ll.release(self.permits);
+    }
+}
+
+impl Drop for OwnedSemaphorePermit {
+    fn drop(&mut self) {
+        self.semaphore.ll.release(self.permits);
+    }
+}
+
+impl fmt::Debug for SemaphorePermit<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SemaphorePermit")
+            .field("permits", &self.permits)
+            .finish()
+    }
+}
+
+impl fmt::Debug for OwnedSemaphorePermit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OwnedSemaphorePermit")
+            .field("permits", &self.permits)
+            .finish()
+    }
+}
+
+impl fmt::Display for AcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Reason::Closed => write!(f, "semaphore closed"),
+            Reason::TooManyPermits => write!(f, "more permits requested than a semaphore can hold"),
+        }
+    }
+}
+
+impl Error for AcquireError {}
+
+impl fmt::Display for TryAcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryAcquireError::Closed => write!(f, "semaphore closed"),
+            TryAcquireError::NoPermits => write!(f, "no permits available"),
+        }
+    }
+}
+
+impl Error for TryAcquireError {}
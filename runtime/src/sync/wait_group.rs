@@ -0,0 +1,50 @@
+use super::Notify;
+use std::sync::Mutex;
+
+/// Waits for a group of tasks to finish, like Go's `sync.WaitGroup`:
+/// `add` before spawning, `done` when a task finished, `wait` for all of them.
+///
+/// Unlike a `CountDownLatch` the counter can go up again after it reached
+/// zero, so the group can be reused.
+#[derive(Debug, Default)]
+pub struct WaitGroup {
+    count: Mutex<usize>,
+    notify: Notify,
+}
+
+impl WaitGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, n: usize) {
+        *self.count.lock().unwrap() += n;
+    }
+
+    /// Panics if called more often than tasks were added
+    pub fn done(&self) {
+        let mut count = self.count.lock().unwrap();
+        *count = count
+            .checked_sub(1)
+            .expect("WaitGroup::done called more often than add");
+        if *count == 0 {
+            drop(count);
+            self.notify.notify_waiters();
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        *self.count.lock().unwrap()
+    }
+
+    /// Wait until the counter is zero
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.count() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Single-producer, multi-consumer channel that only keeps the latest value,
+//! e.g. for configuration that can change at runtime.
+//!
+//! Receivers look at the current value with `borrow` and wait for the next
+//! change with `changed`. Changes that happen while a receiver isn't looking
+//! are coalesced, it only ever sees the latest value.
+use crate::coop;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    future::poll_fn,
+    ops::Deref,
+    sync::{Arc, Mutex, RwLock, RwLockReadGuard},
+    task::{Poll, Waker},
+};
+
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        value: RwLock::new(init),
+        state: Mutex::new(State {
+            version: 0,
+            sender_gone: false,
+            receivers: 0,
+            next_id: 0,
+            waiters: HashMap::new(),
+        }),
+    });
+    let receiver = Receiver::new(shared.clone(), 0);
+    (Sender { shared }, receiver)
+}
+
+struct Shared<T> {
+    value: RwLock<T>,
+    state: Mutex<State>,
+}
+
+struct State {
+    // Incremented on every send
+    version: u64,
+    sender_gone: bool,
+    receivers: usize,
+    next_id: u64,
+    // Wakers of the receivers waiting in `changed`, by receiver id
+    waiters: HashMap<u64, Waker>,
+}
+
+impl<T> Shared<T> {
+    /// Publish a change of the value
+    fn bump_version(&self) {
+        let wakers: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            state.version += 1;
+            state.waiters.drain().map(|(_, waker)| waker).collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    id: u64,
+    // Version of the value this receiver has seen
+    seen: u64,
+}
+
+/// A borrowed value, the sender can't change it while this exists, so keep it short
+pub struct Ref<'a, T> {
+    guard: RwLockReadGuard<'a, T>,
+}
+
+/// There is no receiver, the value is handed back
+#[derive(Clone, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// The sender is gone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecvError(());
+
+impl<T> Sender<T> {
+    /// Replace the value and notify the receivers. Fails if there are none.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if self.receiver_count() == 0 {
+            return Err(SendError(value));
+        }
+        self.send_replace(value);
+        Ok(())
+    }
+
+    /// Replace the value even without receivers and return the old one
+    pub fn send_replace(&self, value: T) -> T {
+        let old = std::mem::replace(&mut *self.shared.value.write().unwrap(), value);
+        self.shared.bump_version();
+        old
+    }
+
+    /// Modify the value in place and notify the receivers
+    pub fn send_modify(&self, modify: impl FnOnce(&mut T)) {
+        modify(&mut self.shared.value.write().unwrap());
+        self.shared.bump_version();
+    }
+
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read().unwrap(),
+        }
+    }
+
+    /// A new receiver that has seen the current value
+    pub fn subscribe(&self) -> Receiver<T> {
+        let version = self.shared.state.lock().unwrap().version;
+        Receiver::new(self.shared.clone(), version)
+    }
+
+    pub fn receiver_count(&self) -> usize {
+        self.shared.state.lock().unwrap().receivers
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.receiver_count() == 0
+    }
+}
+
+impl<T> Receiver<T> {
+    fn new(shared: Arc<Shared<T>>, seen: u64) -> Self {
+        let id = {
+            let mut state = shared.state.lock().unwrap();
+            state.receivers += 1;
+            state.next_id += 1;
+            state.next_id
+        };
+        Receiver { shared, id, seen }
+    }
+
+    /// Look at the current value without marking it as seen
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref {
+            guard: self.shared.value.read().unwrap(),
+        }
+    }
+
+    /// Look at the current value and mark it as seen
+    pub fn borrow_and_update(&mut self) -> Ref<'_, T> {
+        let guard = self.shared.value.read().unwrap();
+        // The sender bumps the version after it released the write lock, so
+        // this can be one behind the value we hold. The next `changed` then
+        // returns right away, but no change is ever missed.
+        self.seen = self.shared.state.lock().unwrap().version;
+        Ref { guard }
+    }
+
+    /// Whether there is a value that wasn't seen yet
+    pub fn has_changed(&self) -> Result<bool, RecvError> {
+        let state = self.shared.state.lock().unwrap();
+        if state.version != self.seen {
+            return Ok(true);
+        }
+        if state.sender_gone {
+            return Err(RecvError(()));
+        }
+        Ok(false)
+    }
+
+    /// Wait until the value changes and mark it as seen, look at it with
+    /// `borrow`. Fails once the sender is gone.
+    pub async fn changed(&mut self) -> Result<(), RecvError> {
+        poll_fn(|cx| {
+            coop::poll_budgeted(cx, |cx| {
+                let mut state = self.shared.state.lock().unwrap();
+                if state.version != self.seen {
+                    self.seen = state.version;
+                    return Poll::Ready(Ok(()));
+                }
+                if state.sender_gone {
+                    return Poll::Ready(Err(RecvError(())));
+                }
+                match state.waiters.get(&self.id) {
+                    Some(waker) if waker.will_wake(cx.waker()) => (),
+                    _ => {
+                        state.waiters.insert(self.id, cx.waker().clone());
+                    }
+                }
+                Poll::Pending
+            })
+        })
+        .await
+    }
+
+    pub fn mark_unchanged(&mut self) {
+        self.seen = self.shared.state.lock().unwrap().version;
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        Receiver::new(self.shared.clone(), self.seen)
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let wakers: Vec<_> = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.sender_gone = true;
+            state.waiters.drain().map(|(_, waker)| waker).collect()
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.receivers -= 1;
+        // The receiver might be dropped while `changed` waits
+        state.waiters.remove(&self.id);
+    }
+}
+
+impl<T> Deref for Ref<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Ref<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("value", &*self.borrow())
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no receivers")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sender dropped")
+    }
+}
+
+impl Error for RecvError {}
@@ -0,0 +1,106 @@
+//! Helpers for the task that is currently running: giving other tasks a turn
+//! and opting out of the cooperative budget. Tasks with a priority other
+//! than `Normal` are started with a `Builder`.
+use crate::{Executor, coop, runtime};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Let the other ready tasks run before this one continues, e.g. in a long
+/// computation that doesn't await anything else
+pub async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+    .await
+}
+
+/// Poll `future` without the cooperative budget, so its resources never
+/// return `Pending` just to give other tasks a turn. Use with care, this
+/// can starve the other tasks again.
+pub fn unconstrained<F: Future>(future: F) -> Unconstrained<F> {
+    Unconstrained {
+        future: Box::pin(future),
+    }
+}
+
+pub struct Unconstrained<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for Unconstrained<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        coop::unconstrained(|| self.future.as_mut().poll(cx))
+    }
+}
+
+/// Which ready task runs first. Tasks of the same priority run in the
+/// order they were woken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How the executor picks among ready tasks of different priorities, see
+/// `Executor::dispatch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dispatch {
+    /// Always run a task of the highest ready priority
+    Strict,
+    /// Out of `high + normal + low` turns, every priority gets its weight
+    /// in turns while it has ready tasks
+    Weighted { high: u32, normal: u32, low: u32 },
+}
+
+/// Start a task with other options than `spawn` and `Executor::schedule`
+///
+/// ```text
+/// task::Builder::new()
+///     .priority(Priority::Low)
+///     .spawn(async { cleanup().await });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    priority: Priority,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Spawn onto the executor that is running the current task, like
+    /// `runtime::spawn`
+    pub fn spawn(self, future: impl Future<Output = ()> + 'static) {
+        runtime::spawn_with_priority(self.priority, future);
+    }
+
+    /// Schedule onto `executor` before it runs, like `Executor::schedule`
+    pub fn schedule(self, executor: &mut Executor, future: impl Future<Output = ()> + 'static) {
+        executor.schedule_with_priority(self.priority, future);
+    }
+}
@@ -0,0 +1,15 @@
+//! Sleeping, time limits and periodic ticks. Unlike `async_timer::AsyncTimer`,
+//! which spawns a thread per timer, all timers share a single driver thread.
+//!
+//! For tests, the clock can be paused: with `Executor::simulated`, a
+//! scenario of minutes of timers runs in milliseconds.
+mod driver;
+mod interval;
+mod sleep;
+mod timeout;
+
+pub use driver::{advance, now, pause};
+pub(crate) use driver::{is_paused, resume, until_next_deadline};
+pub use interval::{Interval, MissedTickBehavior, interval, interval_at};
+pub use sleep::{Sleep, sleep, sleep_until};
+pub use timeout::{Elapsed, Timeout, timeout, timeout_at};
@@ -0,0 +1,282 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Condvar, Mutex, OnceLock},
+    task::{Context, Poll, Wake, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A single thread that wakes the tasks of all `Sleep`s once their deadline
+/// passed. It waits on a condition variable until the earliest deadline and
+/// is woken early when an earlier one is registered.
+///
+/// A paused clock is a driver without a thread: its time only moves with
+/// `advance`, and the executor advances it to the next deadline whenever
+/// all tasks are idle.
+pub(crate) struct Driver {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    // Ordered by deadline, the id makes equal deadlines unique
+    deadlines: BTreeSet<(Instant, u64)>,
+    timers: HashMap<u64, Timer>,
+    next_id: u64,
+    // The virtual time of a paused clock
+    paused_at: Option<Instant>,
+}
+
+struct Timer {
+    deadline: Instant,
+    fired: bool,
+    waker: Option<Waker>,
+}
+
+// Calls the expiry callback of an `AsyncTimer` when its timer fires
+struct Expire(Mutex<Option<Box<dyn FnOnce() + Send>>>);
+
+static DRIVER: OnceLock<Arc<Driver>> = OnceLock::new();
+
+thread_local! {
+    // Set once the clock of this thread was paused
+    static PAUSED: RefCell<Option<Arc<Driver>>> = const { RefCell::new(None) };
+}
+
+/// Get the timer driver of this thread: the paused clock if there is one,
+/// otherwise the shared driver, starting its thread on first use
+pub(crate) fn driver() -> Arc<Driver> {
+    if let Some(paused) = paused() {
+        return paused;
+    }
+    DRIVER
+        .get_or_init(|| {
+            thread::Builder::new()
+                .name("timer".to_string())
+                .spawn(|| driver().run())
+                .expect("Failed to spawn the timer thread");
+            Arc::new(Driver {
+                state: Mutex::new(State::default()),
+                changed: Condvar::new(),
+            })
+        })
+        .clone()
+}
+
+fn paused() -> Option<Arc<Driver>> {
+    PAUSED.with_borrow(Clone::clone)
+}
+
+/// The current time, the virtual one if the clock of this thread is paused
+pub fn now() -> Instant {
+    match paused() {
+        Some(paused) => paused.now(),
+        None => Instant::now(),
+    }
+}
+
+/// Stop the clock of this thread, for tests of code that waits a lot.
+///
+/// From now on, `now` and the timers of this thread (`sleep`, `interval`,
+/// `timeout` and `async_timer::AsyncTimer`) use a virtual clock. It only
+/// moves with `advance`, or when the executor runs out of ready tasks: then
+/// it jumps to the next deadline right away instead of waiting for it.
+/// Timers that were started before keep running on real time. Pausing the
+/// clock again does nothing, only a dropped `Executor::simulated` that
+/// paused it resumes it.
+pub fn pause() {
+    if paused().is_some() {
+        return;
+    }
+    let paused = Arc::new(Driver {
+        state: Mutex::new(State {
+            paused_at: Some(Instant::now()),
+            ..State::default()
+        }),
+        changed: Condvar::new(),
+    });
+    async_timer::set_clock(Some(paused.clone()));
+    PAUSED.set(Some(paused));
+}
+
+pub(crate) fn is_paused() -> bool {
+    paused().is_some()
+}
+
+/// Go back to real time on this thread. Timers started on the paused clock
+/// are left behind and never fire.
+pub(crate) fn resume() {
+    if PAUSED.take().is_some() {
+        async_timer::set_clock(None);
+    }
+}
+
+/// Move the paused clock of this thread forward and fire the timers that
+/// are due, their tasks run once the current task yields. Panics if the
+/// clock isn't paused.
+pub fn advance(duration: Duration) {
+    let paused = paused().expect("advance needs a paused clock, see time::pause");
+    let now = paused.now();
+    paused.advance_to(now + duration);
+}
+
+/// How far the paused clock of this thread has to `advance` to fire the
+/// next timer, `None` if the clock isn't paused or no timer is pending
+pub(crate) fn until_next_deadline() -> Option<Duration> {
+    let paused = paused()?;
+    let deadline = paused.next_deadline()?;
+    Some(deadline.saturating_duration_since(paused.now()))
+}
+
+impl Driver {
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let wakers = state.fire_due(now);
+            if !wakers.is_empty() {
+                drop(state);
+                wakers.into_iter().for_each(Waker::wake);
+                state = self.state.lock().unwrap();
+                continue;
+            }
+            state = match state.deadlines.first() {
+                Some(&(deadline, _)) => self.changed.wait_timeout(state, deadline - now).unwrap().0,
+                None => self.changed.wait(state).unwrap(),
+            };
+        }
+    }
+
+    fn now(&self) -> Instant {
+        let paused_at = self.state.lock().unwrap().paused_at;
+        paused_at.unwrap_or_else(Instant::now)
+    }
+
+    /// The earliest deadline of a timer that didn't fire yet
+    fn next_deadline(&self) -> Option<Instant> {
+        let state = self.state.lock().unwrap();
+        state.deadlines.first().map(|&(deadline, _)| deadline)
+    }
+
+    /// Set the time of a paused clock, it never goes back, and fire the
+    /// timers that are due
+    fn advance_to(&self, time: Instant) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            let paused_at = state
+                .paused_at
+                .as_mut()
+                .expect("only a paused clock can advance");
+            *paused_at = time.max(*paused_at);
+            let now = *paused_at;
+            state.fire_due(now)
+        };
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    pub(crate) fn register(&self, deadline: Instant) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.timers.insert(
+            id,
+            Timer {
+                deadline,
+                fired: false,
+                waker: None,
+            },
+        );
+        self.insert(&mut state, deadline, id);
+        id
+    }
+
+    /// Move a timer to a new deadline, it can fire again afterwards
+    pub(crate) fn reset(&self, id: u64, deadline: Instant) {
+        let mut state = self.state.lock().unwrap();
+        let timer = state.timers.get_mut(&id).unwrap();
+        let old = std::mem::replace(&mut timer.deadline, deadline);
+        let was_fired = std::mem::replace(&mut timer.fired, false);
+        if !was_fired {
+            state.deadlines.remove(&(old, id));
+        }
+        self.insert(&mut state, deadline, id);
+    }
+
+    pub(crate) fn deregister(&self, id: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(timer) = state.timers.remove(&id)
+            && !timer.fired
+        {
+            state.deadlines.remove(&(timer.deadline, id));
+        }
+    }
+
+    pub(crate) fn poll_fired(&self, id: u64, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state.lock().unwrap();
+        let timer = state.timers.get_mut(&id).unwrap();
+        if timer.fired {
+            return Poll::Ready(());
+        }
+        match &timer.waker {
+            Some(waker) if waker.will_wake(cx.waker()) => (),
+            _ => timer.waker = Some(cx.waker().clone()),
+        }
+        Poll::Pending
+    }
+
+    fn insert(&self, state: &mut State, deadline: Instant, id: u64) {
+        state.deadlines.insert((deadline, id));
+        // The thread only needs to wake up if it now has to fire earlier
+        if state.deadlines.first() == Some(&(deadline, id)) {
+            self.changed.notify_one();
+        }
+    }
+}
+
+impl State {
+    /// Mark the timers with a deadline up to `now` as fired and return
+    /// their wakers, to be woken after the lock is released
+    fn fire_due(&mut self, now: Instant) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while let Some(&(deadline, id)) = self.deadlines.first() {
+            if deadline > now {
+                break;
+            }
+            self.deadlines.pop_first();
+            let timer = self.timers.get_mut(&id).unwrap();
+            timer.fired = true;
+            wakers.extend(timer.waker.take());
+        }
+        wakers
+    }
+}
+
+/// Lets `AsyncTimer`s run on a paused clock
+impl async_timer::Clock for Driver {
+    fn schedule(&self, duration: Duration, expire: Box<dyn FnOnce() + Send>) -> u64 {
+        let id = self.register(self.now() + duration);
+        let waker = Waker::from(Arc::new(Expire(Mutex::new(Some(expire)))));
+        self.state
+            .lock()
+            .unwrap()
+            .timers
+            .get_mut(&id)
+            .unwrap()
+            .waker = Some(waker);
+        id
+    }
+
+    fn cancel(&self, id: u64) {
+        self.deregister(id);
+    }
+}
+
+impl Wake for Expire {
+    fn wake(self: Arc<Self>) {
+        if let Some(expire) = self.0.lock().unwrap().take() {
+            expire();
+        }
+    }
+}
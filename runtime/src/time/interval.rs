@@ -0,0 +1,116 @@
+use super::{Sleep, now, sleep_until};
+use crate::stream::Stream;
+use std::{
+    fmt,
+    future::{Future, poll_fn},
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::{Duration, Instant},
+};
+
+/// What an `Interval` does when ticks were missed because the task didn't
+/// call `tick` in time. A tick counts as missed once the deadline after it
+/// passed as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedTickBehavior {
+    /// Fire the missed ticks right away until the interval caught up,
+    /// then continue on the original schedule
+    #[default]
+    Burst,
+    /// Continue one period after the late tick, the schedule is shifted
+    Delay,
+    /// Drop the missed ticks and continue with the next deadline of the
+    /// original schedule
+    Skip,
+}
+
+/// Ticks at fixed deadlines `start`, `start + period`, `start + 2 * period`, ...
+///
+/// Unlike awaiting a new timer per iteration, the time the task needs
+/// between two ticks doesn't add up, so the interval doesn't drift.
+pub struct Interval {
+    next: Instant,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+    // Reset to every next deadline
+    sleep: Sleep,
+}
+
+/// Tick right away and then every `period`, panics if `period` is zero
+pub fn interval(period: Duration) -> Interval {
+    interval_at(now(), period)
+}
+
+/// Tick at `start` and then every `period`, panics if `period` is zero
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    assert!(!period.is_zero(), "interval period must not be zero");
+    Interval {
+        next: start,
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+        sleep: sleep_until(start),
+    }
+}
+
+impl Interval {
+    /// Wait for the next tick and return its deadline
+    pub async fn tick(&mut self) -> Instant {
+        poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        ready!(Pin::new(&mut self.sleep).poll(cx));
+        let tick = self.next;
+        let now = now();
+        self.next = tick + self.period;
+        if now >= self.next {
+            self.next = match self.missed_tick_behavior {
+                MissedTickBehavior::Burst => self.next,
+                MissedTickBehavior::Delay => now + self.period,
+                MissedTickBehavior::Skip => {
+                    let behind = (now - tick).as_nanos() % self.period.as_nanos();
+                    now + self.period - Duration::from_nanos(behind as u64)
+                }
+            };
+        }
+        self.sleep.reset(self.next);
+        Poll::Ready(tick)
+    }
+
+    /// Start over, the next tick is one period from now
+    pub fn reset(&mut self) {
+        self.next = now() + self.period;
+        self.sleep.reset(self.next);
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
+
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+}
+
+/// Never ends, yields the deadline of every tick
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        self.get_mut().poll_tick(cx).map(Some)
+    }
+}
+
+impl fmt::Debug for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interval")
+            .field("next", &self.next)
+            .field("period", &self.period)
+            .field("missed_tick_behavior", &self.missed_tick_behavior)
+            .finish()
+    }
+}
@@ -0,0 +1,87 @@
+use super::driver::{Driver, driver, now};
+use crate::coop;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// Completes once its deadline passed.
+///
+/// The deadline is fixed when the `Sleep` is created, not when it's first
+/// polled, and it can be moved with `reset` while the `Sleep` is pending.
+pub struct Sleep {
+    deadline: Instant,
+    // Set once registered with the timer driver on first poll. A `Sleep`
+    // stays with the driver it started on, even if the clock is paused later.
+    timer: Option<(Arc<Driver>, u64)>,
+}
+
+/// Sleep for `duration`, counted from now
+pub fn sleep(duration: Duration) -> Sleep {
+    sleep_until(now() + duration)
+}
+
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    Sleep {
+        deadline,
+        timer: None,
+    }
+}
+
+impl Sleep {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn is_elapsed(&self) -> bool {
+        now() >= self.deadline
+    }
+
+    /// Move the deadline, also after the `Sleep` completed. The same timer
+    /// is reused, nothing is allocated.
+    pub fn reset(&mut self, deadline: Instant) {
+        self.deadline = deadline;
+        if let Some((driver, id)) = &self.timer {
+            driver.reset(*id, deadline);
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        coop::poll_budgeted(cx, |cx| {
+            if self.timer.is_none() {
+                if self.is_elapsed() {
+                    return Poll::Ready(());
+                }
+                let driver = driver();
+                let id = driver.register(self.deadline);
+                self.timer = Some((driver, id));
+            }
+            let (driver, id) = self.timer.as_ref().unwrap();
+            driver.poll_fired(*id, cx)
+        })
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some((driver, id)) = &self.timer {
+            driver.deregister(*id);
+        }
+    }
+}
+
+impl fmt::Debug for Sleep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sleep")
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Sleep, now, sleep_until};
+use std::{
+    error::Error,
+    fmt,
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+/// The future didn't complete before its deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed(());
+
+/// Future returned by `timeout` and `timeout_at`
+pub struct Timeout<F> {
+    future: Pin<Box<F>>,
+    // Deregistered from the timer driver as soon as the future completes
+    sleep: Option<Sleep>,
+    deadline: Instant,
+}
+
+/// Wait for `future`, but at most `duration` from now
+pub fn timeout<F: Future>(duration: Duration, future: F) -> Timeout<F> {
+    timeout_at(now() + duration, future)
+}
+
+/// Wait for `future`, but only until `deadline`
+pub fn timeout_at<F: Future>(deadline: Instant, future: F) -> Timeout<F> {
+    Timeout {
+        future: Box::pin(future),
+        sleep: Some(sleep_until(deadline)),
+        deadline,
+    }
+}
+
+impl<F> Timeout<F> {
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn get_ref(&self) -> &F {
+        &self.future
+    }
+
+    /// Drop the time limit and get the future back
+    pub fn into_inner(self) -> Pin<Box<F>> {
+        self.future
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Output = Result<F::Output, Elapsed>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        // A future that is ready wins even if the deadline just passed
+        if let Poll::Ready(output) = this.future.as_mut().poll(cx) {
+            this.sleep = None;
+            return Poll::Ready(Ok(output));
+        }
+        let Some(sleep) = &mut this.sleep else {
+            return Poll::Ready(Err(Elapsed(())));
+        };
+        match Pin::new(sleep).poll(cx) {
+            Poll::Ready(()) => {
+                this.sleep = None;
+                Poll::Ready(Err(Elapsed(())))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F> fmt::Debug for Timeout<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timeout")
+            .field("deadline", &self.deadline)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "deadline has elapsed")
+    }
+}
+
+impl Error for Elapsed {}
+
+impl From<Elapsed> for io::Error {
+    fn from(elapsed: Elapsed) -> io::Error {
+        io::Error::new(io::ErrorKind::TimedOut, elapsed)
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    spawn,
+    sync::broadcast::{self, RecvError, TryRecvError},
+    task::yield_now,
+};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn every_receiver_gets_every_value() {
+    let (tx, mut a) = broadcast::channel(4);
+    let mut b = tx.subscribe();
+    assert_eq!(tx.send(1), Ok(2));
+    tx.send(2).unwrap();
+    for rx in [&mut a, &mut b] {
+        assert_eq!(rx.try_recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+}
+
+#[test]
+fn lagging_receiver_skips_to_the_oldest_buffered_value() {
+    block_on(async {
+        let (tx, mut rx) = broadcast::channel(2);
+        for n in 0..5 {
+            tx.send(n).unwrap();
+        }
+        // Only 3 and 4 are still buffered
+        assert_eq!(rx.recv().await, Err(RecvError::Lagged(3)));
+        assert_eq!(rx.recv().await, Ok(3));
+        assert_eq!(rx.recv().await, Ok(4));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    });
+}
+
+#[test]
+fn closed_only_once_the_buffer_is_drained() {
+    block_on(async {
+        let (tx, mut rx) = broadcast::channel(4);
+        let second = tx.clone();
+        tx.send("a").unwrap();
+        second.send("b").unwrap();
+        drop(tx);
+        assert_eq!(rx.try_recv(), Ok("a"));
+        drop(second);
+        assert_eq!(rx.recv().await, Ok("b"));
+        assert_eq!(rx.recv().await, Err(RecvError::Closed));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Closed));
+    });
+}
+
+#[test]
+fn subscribers_start_at_the_tail() {
+    let (tx, mut rx) = broadcast::channel(4);
+    tx.send(1).unwrap();
+    let mut subscribed = tx.subscribe();
+    let mut resubscribed = rx.resubscribe();
+    tx.send(2).unwrap();
+    assert_eq!(subscribed.try_recv(), Ok(2));
+    assert_eq!(resubscribed.try_recv(), Ok(2));
+    // The original receiver still has the older value
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert_eq!(tx.receiver_count(), 3);
+}
+
+#[test]
+fn send_fails_without_receivers() {
+    let (tx, rx) = broadcast::channel(1);
+    drop(rx);
+    assert_eq!(tx.send(5).unwrap_err().0, 5);
+}
+
+#[test]
+fn waiting_receivers_are_woken_by_send() {
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let log = received.clone();
+    block_on(async move {
+        let (tx, rx) = broadcast::channel(4);
+        for (name, mut rx) in [("a", rx.resubscribe()), ("b", rx)] {
+            let log = log.clone();
+            spawn(async move {
+                while let Ok(n) = rx.recv().await {
+                    log.borrow_mut().push(format!("{name}{n}"));
+                }
+            });
+        }
+        for n in 0..2 {
+            yield_now().await;
+            tx.send(n).unwrap();
+        }
+    });
+    let mut received = received.take();
+    received.sort();
+    assert_eq!(received, ["a0", "a1", "b0", "b1"]);
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    spawn,
+    sync::{mpsc, oneshot},
+    task::yield_now,
+};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn oneshot_delivers_the_value_to_another_task() {
+    let value = block_on(async {
+        let (tx, rx) = oneshot::channel();
+        spawn(async move {
+            yield_now().await;
+            tx.send("done").unwrap();
+        });
+        rx.await
+    });
+    assert_eq!(value, Ok("done"));
+}
+
+#[test]
+fn oneshot_reports_a_dropped_sender_and_receiver() {
+    block_on(async {
+        let (tx, rx) = oneshot::channel::<u32>();
+        spawn(async move {
+            yield_now().await;
+            drop(tx);
+        });
+        assert!(rx.await.is_err());
+
+        let (mut tx, rx) = oneshot::channel::<u32>();
+        spawn(async move {
+            yield_now().await;
+            drop(rx);
+        });
+        tx.closed().await;
+        assert!(tx.is_closed());
+        assert_eq!(tx.send(1), Err(1));
+    });
+}
+
+#[test]
+fn bounded_channel_applies_backpressure_to_the_producer() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let producer_log = log.clone();
+    let consumer_log = log.clone();
+    block_on(async move {
+        let log = consumer_log;
+        let (tx, mut rx) = mpsc::channel(2);
+        spawn(async move {
+            for n in 0..5 {
+                tx.send(n).await.unwrap();
+                producer_log.borrow_mut().push(format!("sent {n}"));
+            }
+        });
+        // Let the producer run until the channel is full
+        for _ in 0..3 {
+            yield_now().await;
+        }
+        assert_eq!(*log.borrow(), ["sent 0", "sent 1"]);
+        while let Some(n) = rx.recv().await {
+            log.borrow_mut().push(format!("received {n}"));
+        }
+    });
+    let log = log.take();
+    assert_eq!(log.len(), 10);
+    // The producer never got more than the capacity ahead of the consumer
+    for n in 2..5 {
+        let sent = log.iter().position(|line| *line == format!("sent {n}"));
+        let freed = log
+            .iter()
+            .position(|line| *line == format!("received {}", n - 2));
+        assert!(freed < sent, "{log:?}");
+    }
+}
+
+#[test]
+fn try_send_fails_when_full_or_closed() {
+    block_on(async {
+        let (tx, mut rx) = mpsc::channel(1);
+        assert!(tx.try_send(1).is_ok());
+        assert!(matches!(tx.try_send(2), Err(mpsc::TrySendError::Full(2))));
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(tx.try_send(3).is_ok());
+        rx.close();
+        assert!(tx.is_closed());
+        assert!(matches!(tx.try_send(4), Err(mpsc::TrySendError::Closed(4))));
+        // Values queued before the close can still be received
+        assert_eq!(rx.recv().await, Some(3));
+        assert_eq!(rx.recv().await, None);
+    });
+}
+
+#[test]
+fn receiver_sees_the_end_once_all_senders_dropped() {
+    let received = block_on(async {
+        let (tx, mut rx) = mpsc::channel(1);
+        for id in 0..3 {
+            let tx = tx.clone();
+            spawn(async move {
+                for n in 0..2 {
+                    tx.send(id * 10 + n).await.unwrap();
+                    yield_now().await;
+                }
+            });
+        }
+        drop(tx);
+        let mut received = Vec::new();
+        while let Some(n) = rx.recv().await {
+            received.push(n);
+        }
+        assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+        received
+    });
+    let mut sorted = received.clone();
+    sorted.sort();
+    assert_eq!(sorted, [0, 1, 10, 11, 20, 21]);
+}
+
+#[test]
+fn unbounded_channel_never_blocks_the_producer() {
+    block_on(async {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for n in 0..100 {
+            tx.send(n).unwrap();
+        }
+        assert_eq!(rx.try_recv(), Ok(0));
+        spawn(async move {
+            yield_now().await;
+            drop(tx);
+        });
+        let mut count = 1;
+        while rx.recv().await.is_some() {
+            count += 1;
+        }
+        assert_eq!(count, 100);
+    });
+}
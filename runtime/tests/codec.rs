@@ -0,0 +1,154 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    codec::{BytesCodec, Decoder, FramedRead, LengthDelimitedCodec, LinesCodec, LinesCodecError},
+    io::AsyncRead,
+    stream::StreamExt,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Hands out `bytes` at most `step` bytes per read
+struct Trickle {
+    bytes: &'static [u8],
+    step: usize,
+}
+
+impl AsyncRead for Trickle {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let n = self.bytes.len().min(self.step).min(buf.len());
+        buf[..n].copy_from_slice(&self.bytes[..n]);
+        self.bytes = &self.bytes[n..];
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// Every item of the stream decoded from `bytes`, read `step` bytes at a time
+fn decode_all<D>(bytes: &'static [u8], step: usize, decoder: D) -> Vec<Result<D::Item, D::Error>>
+where
+    D: Decoder + 'static,
+    D::Item: 'static,
+    D::Error: 'static,
+{
+    block_on(async move {
+        let mut frames = FramedRead::new(Trickle { bytes, step }, decoder);
+        let mut items = Vec::new();
+        while let Some(item) = frames.next().await {
+            items.push(item);
+        }
+        items
+    })
+}
+
+#[test]
+fn lines_split_across_reads() {
+    for step in 1..6 {
+        let lines: Vec<_> = decode_all(b"hello\r\nworld\n\nlast", step, LinesCodec::new())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(lines, ["hello", "world", "", "last"], "step {step}");
+    }
+}
+
+#[test]
+fn a_line_over_the_max_length_fails_before_it_ends() {
+    let mut codec = LinesCodec::new_with_max_length(4);
+    let mut src = b"abc".to_vec();
+    assert!(codec.decode(&mut src).unwrap().is_none());
+    src.extend_from_slice(b"de");
+    assert!(matches!(
+        codec.decode(&mut src),
+        Err(LinesCodecError::MaxLineLengthExceeded)
+    ));
+}
+
+#[test]
+fn length_delimited_frames_split_across_reads() {
+    let bytes = b"\x00\x00\x00\x05hello\x00\x00\x00\x00\x00\x00\x00\x02hi";
+    for step in 1..6 {
+        let frames: Vec<_> = decode_all(bytes, step, LengthDelimitedCodec::new())
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(frames, [&b"hello"[..], b"", b"hi"], "step {step}");
+    }
+}
+
+#[test]
+fn partial_header_and_payload_wait_for_more_bytes() {
+    let mut codec = LengthDelimitedCodec::new()
+        .length_field_length(2)
+        .little_endian();
+    let mut src = vec![3];
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    src.extend_from_slice(&[0, b'a', b'b']);
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    src.extend_from_slice(b"c\x01");
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(b"abc".to_vec()));
+    // The start of the next frame stays buffered
+    assert_eq!(src, [1]);
+}
+
+#[test]
+fn a_length_over_the_max_frame_length_fails_right_away() {
+    let mut codec = LengthDelimitedCodec::new().max_frame_length(4);
+    let mut src = b"\x00\x00\x00\x05".to_vec();
+    let e = codec.decode(&mut src).unwrap_err();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+    let mut src = b"\x00\x00\x00\x04abcd".to_vec();
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(b"abcd".to_vec()));
+}
+
+#[test]
+fn a_length_over_the_max_frame_length_ends_the_stream() {
+    let codec = LengthDelimitedCodec::new().max_frame_length(4);
+    let items = decode_all(b"\x00\x00\x00\x02ok\xff\xff\xff\xffdata", 3, codec);
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), b"ok");
+    assert_eq!(
+        items[1].as_ref().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}
+
+#[test]
+fn eof_within_a_frame_fails() {
+    let items = decode_all(
+        b"\x00\x00\x00\x02ok\x00\x00\x00\x05abc",
+        2,
+        LengthDelimitedCodec::new(),
+    );
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), b"ok");
+    assert_eq!(
+        items[1].as_ref().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+
+    // Also within the header
+    let items = decode_all(b"\x00\x00", 1, LengthDelimitedCodec::new());
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].as_ref().unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+}
+
+#[test]
+fn bytes_come_as_they_were_read() {
+    let chunks: Vec<_> = decode_all(b"abcdefg", 3, BytesCodec::new())
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(chunks, [&b"abc"[..], b"def", b"g"]);
+}
@@ -0,0 +1,50 @@
+//! Helpers shared by the integration tests
+#![allow(dead_code)]
+
+use runtime::Executor;
+use std::{
+    cell::RefCell,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Run `future` on a new `Executor` until everything it spawned is done
+/// and return its output
+pub fn block_on<T: 'static>(future: impl Future<Output = T> + 'static) -> T {
+    let output = Rc::new(RefCell::new(None));
+    let mut executor = Executor::new();
+    let slot = output.clone();
+    executor.schedule(async move {
+        *slot.borrow_mut() = Some(future.await);
+    });
+    executor.block();
+    output.take().expect("the future didn't complete")
+}
+
+/// A fresh directory that is removed again on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("runtime-test-{}-{n}", process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{TempDir, block_on};
+use runtime::{
+    fs::{self, IN_ALL_CHANGES, IN_DELETE_SELF, IN_MOVE_SELF, WatchEvent, WatchEventKind},
+    io::AsyncFd,
+    time,
+};
+use std::{cell::Cell, io::Write, os::unix::net::UnixStream, path::Path, rc::Rc, time::Duration};
+
+async fn next(watcher: &mut fs::Watcher) -> WatchEvent {
+    watcher.next().await.expect("the watch ended").unwrap()
+}
+
+fn named(event: &WatchEvent) -> (WatchEventKind, Option<&Path>) {
+    (event.kind, event.name.as_deref())
+}
+
+#[test]
+fn reports_created_modified_and_deleted_files() {
+    let dir = TempDir::new();
+    let path = dir.path().to_owned();
+    block_on(async move {
+        let mut watcher = fs::watch(&path, IN_ALL_CHANGES).unwrap();
+        std::fs::write(path.join("config.toml"), "a = 1").unwrap();
+        let file = Some(Path::new("config.toml"));
+        assert_eq!(
+            named(&next(&mut watcher).await),
+            (WatchEventKind::Created, file)
+        );
+        assert_eq!(
+            named(&next(&mut watcher).await),
+            (WatchEventKind::Modified, file)
+        );
+        std::fs::remove_file(path.join("config.toml")).unwrap();
+        let event = next(&mut watcher).await;
+        assert_eq!(named(&event), (WatchEventKind::Deleted, file));
+        assert!(!event.is_dir);
+    });
+}
+
+#[test]
+fn pairs_both_halves_of_a_rename_by_cookie() {
+    let dir = TempDir::new();
+    let path = dir.path().to_owned();
+    std::fs::write(path.join("old"), "").unwrap();
+    block_on(async move {
+        let mut watcher = fs::watch(&path, IN_ALL_CHANGES).unwrap();
+        std::fs::rename(path.join("old"), path.join("new")).unwrap();
+        let from = next(&mut watcher).await;
+        let to = next(&mut watcher).await;
+        assert_eq!(
+            named(&from),
+            (WatchEventKind::MovedFrom, Some(Path::new("old")))
+        );
+        assert_eq!(
+            named(&to),
+            (WatchEventKind::MovedTo, Some(Path::new("new")))
+        );
+        assert_eq!(from.cookie, to.cookie);
+        assert_ne!(from.cookie, 0);
+    });
+}
+
+#[test]
+fn reports_the_watched_path_moving_and_the_end_of_the_watch() {
+    let dir = TempDir::new();
+    let path = dir.path().to_owned();
+    std::fs::write(path.join("watched"), "").unwrap();
+    block_on(async move {
+        let mut watcher = fs::watch(path.join("watched"), IN_MOVE_SELF | IN_DELETE_SELF).unwrap();
+        std::fs::rename(path.join("watched"), path.join("moved")).unwrap();
+        assert_eq!(
+            named(&next(&mut watcher).await),
+            (WatchEventKind::MovedSelf, None)
+        );
+        std::fs::remove_file(path.join("moved")).unwrap();
+        assert_eq!(
+            named(&next(&mut watcher).await),
+            (WatchEventKind::Deleted, None)
+        );
+        assert!(watcher.next().await.is_none());
+    });
+}
+
+#[test]
+fn watching_a_missing_path_fails() {
+    let dir = TempDir::new();
+    let err = fs::watch(dir.path().join("missing"), IN_ALL_CHANGES)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn wakes_every_task_waiting_on_the_same_fd() {
+    let (reader, mut writer) = UnixStream::pair().unwrap();
+    reader.set_nonblocking(true).unwrap();
+    let fd = Rc::new(AsyncFd::new(reader).unwrap());
+    let woken = Rc::new(Cell::new(0));
+    block_on(async move {
+        for _ in 0..2 {
+            let fd = fd.clone();
+            let woken = woken.clone();
+            runtime::spawn(async move {
+                let _guard = fd.readable().await.unwrap();
+                woken.set(woken.get() + 1);
+            });
+        }
+        // Both tasks are waiting before the data arrives
+        runtime::task::yield_now().await;
+        writer.write_all(b"x").unwrap();
+        let both_woken = async {
+            while woken.get() < 2 {
+                runtime::task::yield_now().await;
+            }
+        };
+        let res = time::timeout(Duration::from_secs(5), both_woken).await;
+        assert!(res.is_ok(), "only {} of 2 tasks were woken", woken.get());
+    });
+}
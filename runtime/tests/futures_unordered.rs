@@ -0,0 +1,70 @@
+mod common;
+
+use common::block_on;
+use runtime::{future::FuturesUnordered, task::yield_now};
+use std::task::{Context, Poll, Waker};
+
+/// Ready with `value` after `yields` polls
+async fn after(yields: usize, value: u32) -> u32 {
+    for _ in 0..yields {
+        yield_now().await;
+    }
+    value
+}
+
+#[test]
+fn outputs_come_in_the_order_the_futures_complete() {
+    let order = block_on(async {
+        let mut set: FuturesUnordered<_> = [after(3, 3), after(1, 1), after(0, 0), after(2, 2)]
+            .into_iter()
+            .collect();
+        let mut order = Vec::new();
+        while let Some(value) = set.next().await {
+            order.push(value);
+        }
+        order
+    });
+    assert_eq!(order, [0, 1, 2, 3]);
+}
+
+#[test]
+fn futures_pushed_while_iterating_are_polled() {
+    let order = block_on(async {
+        let mut set = FuturesUnordered::new();
+        set.push(after(1, 0));
+        let mut order = Vec::new();
+        while let Some(value) = set.next().await {
+            order.push(value);
+            if value < 3 {
+                set.push(after(1, value + 1));
+            }
+        }
+        order
+    });
+    assert_eq!(order, [0, 1, 2, 3]);
+}
+
+#[test]
+fn a_future_pushed_while_the_set_is_pending_is_polled_next_time() {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut set = FuturesUnordered::new();
+    set.push(after(usize::MAX, 0));
+    assert_eq!(set.poll_next(&mut cx), Poll::Pending);
+    set.push(after(0, 1));
+    assert_eq!(set.poll_next(&mut cx), Poll::Ready(Some(1)));
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn an_empty_set_yields_none() {
+    block_on(async {
+        let mut set = FuturesUnordered::<std::future::Ready<u32>>::new();
+        assert!(set.is_empty());
+        assert_eq!(set.next().await, None);
+        set.push(std::future::ready(1));
+        assert_eq!(set.next().await, Some(1));
+        // Drained, it ends again instead of waiting
+        assert_eq!(set.next().await, None);
+        assert!(set.is_empty());
+    });
+}
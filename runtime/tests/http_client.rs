@@ -0,0 +1,198 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    http::Client,
+    net::{TcpListener, TcpStream},
+};
+use std::{cell::RefCell, io, rc::Rc};
+
+/// Requests as the server received them, head and body
+type Received = Rc<RefCell<Vec<String>>>;
+
+/// Serve one connection per entry of `connections`, answering its requests
+/// with the raw responses in order and closing it afterwards. Returns the
+/// base url.
+fn serve(connections: Vec<Vec<&'static str>>) -> (String, Received) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let received = Received::default();
+    let log = received.clone();
+    runtime::spawn(async move {
+        for responses in connections {
+            let (stream, _) = listener.accept().await.unwrap();
+            for response in responses {
+                let request = read_request(&stream).await.unwrap();
+                log.borrow_mut().push(request);
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        }
+    });
+    (url, received)
+}
+
+async fn read_request(stream: &TcpStream) -> io::Result<String> {
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&data[..end]).to_lowercase();
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length: "))
+                .map_or(0, |length| length.parse().unwrap());
+            if data.len() >= end + 4 + length {
+                return Ok(String::from_utf8(data).unwrap());
+            }
+        }
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+}
+
+const HELLO: &str = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello";
+
+#[test]
+fn gets_a_response_with_content_length() {
+    block_on(async {
+        let (url, received) = serve(vec![vec![HELLO]]);
+        let client = Client::new();
+        let response = client.get(&format!("{url}/1000/hi")).await.unwrap();
+        assert_eq!((response.status, response.reason.as_str()), (200, "OK"));
+        assert_eq!(response.header("content-length"), Some("5"));
+        assert_eq!(response.text(), "hello");
+        let request = received.borrow()[0].clone();
+        assert!(request.starts_with("GET /1000/hi HTTP/1.1\r\n"));
+        let host = url.strip_prefix("http://").unwrap();
+        assert!(request.contains(&format!("Host: {host}\r\n")));
+    });
+}
+
+#[test]
+fn decodes_chunked_and_close_delimited_bodies() {
+    block_on(async {
+        let (url, _) = serve(vec![
+            vec![
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\nTrailer: x\r\n\r\n",
+            ],
+            vec!["HTTP/1.0 200 OK\r\n\r\nuntil the end"],
+        ]);
+        let client = Client::new();
+        let chunked = client.get(&url).await.unwrap();
+        assert_eq!(chunked.text(), "hello world");
+        let until_close = client.get(&url).await.unwrap();
+        assert_eq!(until_close.text(), "until the end");
+    });
+}
+
+#[test]
+fn reuses_keep_alive_connections() {
+    block_on(async {
+        // A single connection that answers both requests
+        let (url, received) = serve(vec![vec![HELLO, HELLO]]);
+        let client = Client::new();
+        for _ in 0..2 {
+            assert_eq!(client.get(&url).await.unwrap().text(), "hello");
+        }
+        assert_eq!(received.borrow().len(), 2);
+    });
+}
+
+#[test]
+fn sends_the_body_with_its_length() {
+    block_on(async {
+        let (url, received) = serve(vec![vec![HELLO]]);
+        let client = Client::new();
+        client
+            .post(&url)
+            .header("Content-Type", "text/plain")
+            .body("ping")
+            .await
+            .unwrap();
+        let request = received.borrow()[0].clone();
+        assert!(request.starts_with("POST / HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Length: 4\r\n"));
+        assert!(request.ends_with("\r\n\r\nping"));
+    });
+}
+
+#[test]
+fn retries_an_idempotent_request_on_a_stale_connection() {
+    block_on(async {
+        // The server closes the first connection after one response
+        let (url, received) = serve(vec![vec![HELLO], vec![HELLO]]);
+        let client = Client::new();
+        client.get(&url).await.unwrap();
+        let response = client.get(&url).await.unwrap();
+        assert_eq!(response.text(), "hello");
+        assert_eq!(received.borrow().len(), 2);
+    });
+}
+
+#[test]
+fn does_not_retry_a_post_on_a_stale_connection() {
+    block_on(async {
+        let (url, received) = serve(vec![vec![HELLO]]);
+        let client = Client::new();
+        client.get(&url).await.unwrap();
+        let err = client.post(&url).body("once").await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::BrokenPipe
+        ));
+        assert_eq!(received.borrow().len(), 1);
+    });
+}
+
+#[test]
+fn rejects_unsupported_urls() {
+    block_on(async {
+        let client = Client::new();
+        let err = client.get("https://localhost/").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = client.get("http://localhost:http/").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    });
+}
+
+#[test]
+fn fails_on_a_response_body_over_the_limit() {
+    block_on(async {
+        let (url, _) = serve(vec![
+            vec![HELLO],
+            vec!["HTTP/1.1 200 OK\r\nConnection: close\r\n\r\nhello"],
+        ]);
+        let client = Client::new().max_body_size(4);
+        let err = client.get(&url).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // Also when the length is only known once the server closes
+        let err = client.get(&url).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    });
+}
+
+#[test]
+fn rejects_line_breaks_in_headers() {
+    block_on(async {
+        let (url, received) = serve(vec![vec![HELLO]]);
+        let client = Client::new();
+        let err = client
+            .get(&url)
+            .header("X-Name", "a\r\nInjected: 1")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = client.get(&url).header("X\nName", "a").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // Nothing was sent, the connection is still free for a valid request
+        client.get(&url).header("X-Name", "a").await.unwrap();
+        assert_eq!(received.borrow().len(), 1);
+        assert!(!received.borrow()[0].contains("Injected"));
+    });
+}
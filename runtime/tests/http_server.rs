@@ -0,0 +1,174 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    http::{Client, Request, Response, Server},
+    net::TcpStream,
+    sync::oneshot,
+    time,
+};
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+async fn echo(request: Request) -> Response {
+    let body = format!("{} {} {}", request.method, request.path, request.body.len());
+    Response::new(200, body)
+}
+
+/// Start `server` with the `echo` service, returns its address, a trigger for
+/// the graceful shutdown and a flag that is set once the server stopped
+fn start(server: Server) -> (String, oneshot::Sender<()>, Rc<Cell<bool>>) {
+    let addr = server.local_addr().unwrap().to_string();
+    let (shutdown, signal) = oneshot::channel();
+    let stopped = Rc::new(Cell::new(false));
+    let flag = stopped.clone();
+    runtime::spawn(async move {
+        let signal = async {
+            let _ = signal.await;
+        };
+        server.serve_with_shutdown(echo, signal).await.unwrap();
+        flag.set(true);
+    });
+    (addr, shutdown, stopped)
+}
+
+fn server() -> Server {
+    Server::bind("127.0.0.1:0").unwrap()
+}
+
+/// Send raw bytes and read everything until the server closes the connection
+async fn exchange(addr: &str, request: &[u8]) -> String {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(request).await.unwrap();
+    read_to_close(&stream).await
+}
+
+async fn read_to_close(stream: &TcpStream) -> String {
+    let mut response = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return String::from_utf8_lossy(&response).into_owned(),
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+        }
+    }
+}
+
+#[test]
+fn serves_requests_on_a_keep_alive_connection() {
+    block_on(async {
+        let (addr, shutdown, _) = start(server());
+        let client = Client::new();
+        let url = format!("http://{addr}");
+        let response = client.get(&format!("{url}/a")).await.unwrap();
+        assert_eq!(response.text(), "GET /a 0");
+        let response = client
+            .post(&format!("{url}/b"))
+            .body("12345")
+            .await
+            .unwrap();
+        assert_eq!(response.text(), "POST /b 5");
+        shutdown.send(()).unwrap();
+    });
+}
+
+#[test]
+fn answers_pipelined_requests_in_order() {
+    block_on(async {
+        let (addr, shutdown, _) = start(server());
+        let response = exchange(
+            &addr,
+            b"GET /1 HTTP/1.1\r\n\r\nGET /2 HTTP/1.1\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        let first = response.find("GET /1 0").unwrap();
+        let second = response.find("GET /2 0").unwrap();
+        assert!(first < second);
+        shutdown.send(()).unwrap();
+    });
+}
+
+#[test]
+fn rejects_a_body_over_the_limit_before_reading_it() {
+    block_on(async {
+        let (addr, shutdown, _) = start(server().max_body_size(1024));
+        // The announced body is never sent, the answer can't wait for it
+        let response = exchange(
+            &addr,
+            b"POST / HTTP/1.1\r\nContent-Length: 1000000000\r\nExpect: 100-continue\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+        assert!(!response.contains("100 Continue"));
+
+        let chunks = "400\r\n".to_string() + &"x".repeat(1024) + "\r\n1\r\ny\r\n0\r\n\r\n";
+        let request = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n".to_string() + &chunks;
+        let response = exchange(&addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+
+        let request = "POST / HTTP/1.1\r\nContent-Length: 1024\r\nConnection: close\r\n\r\n"
+            .to_string()
+            + &"x".repeat(1024);
+        let response = exchange(&addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 200 "), "{response}");
+        shutdown.send(()).unwrap();
+    });
+}
+
+#[test]
+fn rejects_a_head_over_the_limit() {
+    block_on(async {
+        let (addr, shutdown, _) = start(server().max_head_size(256));
+        let request = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(512));
+        let response = exchange(&addr, request.as_bytes()).await;
+        assert!(response.starts_with("HTTP/1.1 431 "), "{response}");
+        shutdown.send(()).unwrap();
+    });
+}
+
+#[test]
+fn rejects_ambiguous_framing() {
+    block_on(async {
+        let (addr, shutdown, _) = start(server());
+        let response = exchange(
+            &addr,
+            b"POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 400 "), "{response}");
+        shutdown.send(()).unwrap();
+    });
+}
+
+#[test]
+fn shutdown_finishes_a_partly_received_request() {
+    block_on(async {
+        let (addr, shutdown, stopped) = start(server());
+        let stream = TcpStream::connect(addr.as_str()).await.unwrap();
+        stream.write_all(b"GET /late HTTP/1.1\r\nHo").await.unwrap();
+        time::sleep(Duration::from_millis(50)).await;
+        shutdown.send(()).unwrap();
+        time::sleep(Duration::from_millis(50)).await;
+        assert!(!stopped.get());
+        stream.write_all(b"st: x\r\n\r\n").await.unwrap();
+        let response = read_to_close(&stream).await;
+        assert!(response.starts_with("HTTP/1.1 200 "), "{response}");
+        assert!(response.contains("Connection: close"));
+        assert!(response.ends_with("GET /late 0"));
+    });
+}
+
+#[test]
+fn shutdown_closes_idle_connections() {
+    block_on(async {
+        let (addr, shutdown, stopped) = start(server());
+        let stream = TcpStream::connect(addr.as_str()).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut buf = [0; 1024];
+        assert!(stream.read(&mut buf).await.unwrap() > 0);
+        shutdown.send(()).unwrap();
+        // The connection is idle now, the server closes it and stops
+        assert_eq!(read_to_close(&stream).await, "");
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(stopped.get());
+    });
+}
@@ -0,0 +1,84 @@
+use runtime::{
+    Executor,
+    time::{self, MissedTickBehavior},
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+/// Tick a 10 second interval `ticks` times on a virtual clock, taking
+/// `late` after the first tick before calling `tick` again. Returns the
+/// second of every deadline and the second it fired at.
+fn run(behavior: MissedTickBehavior, late: u64, ticks: usize) -> Vec<(u64, u64)> {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut executor = Executor::simulated(0);
+    let task_log = log.clone();
+    executor.schedule(async move {
+        let start = time::now();
+        let mut interval = time::interval(Duration::from_secs(10));
+        interval.set_missed_tick_behavior(behavior);
+        for tick in 0..ticks {
+            let deadline = interval.tick().await;
+            let secs = |at: std::time::Instant| (at - start).as_secs();
+            task_log
+                .borrow_mut()
+                .push((secs(deadline), secs(time::now())));
+            if tick == 0 {
+                time::advance(Duration::from_secs(late));
+            }
+        }
+    });
+    executor.block();
+    log.take()
+}
+
+#[test]
+fn burst_fires_the_missed_ticks_right_away() {
+    let ticks = run(MissedTickBehavior::Burst, 35, 5);
+    assert_eq!(ticks, [(0, 0), (10, 35), (20, 35), (30, 35), (40, 40)]);
+}
+
+#[test]
+fn delay_continues_one_period_after_the_late_tick() {
+    let ticks = run(MissedTickBehavior::Delay, 35, 4);
+    assert_eq!(ticks, [(0, 0), (10, 35), (45, 45), (55, 55)]);
+}
+
+#[test]
+fn skip_continues_on_the_original_schedule() {
+    let ticks = run(MissedTickBehavior::Skip, 35, 4);
+    assert_eq!(ticks, [(0, 0), (10, 35), (40, 40), (50, 50)]);
+}
+
+#[test]
+fn a_tick_that_is_late_but_not_missed_keeps_the_schedule() {
+    for behavior in [
+        MissedTickBehavior::Burst,
+        MissedTickBehavior::Delay,
+        MissedTickBehavior::Skip,
+    ] {
+        let ticks = run(behavior, 15, 3);
+        assert_eq!(ticks, [(0, 0), (10, 15), (20, 20)], "{behavior:?}");
+    }
+}
+
+#[test]
+fn work_between_ticks_does_not_add_up() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut executor = Executor::simulated(0);
+    let task_log = log.clone();
+    executor.schedule(async move {
+        let start = time::now();
+        let mut interval =
+            time::interval_at(start + Duration::from_secs(5), Duration::from_secs(5));
+        for _ in 0..4 {
+            interval.tick().await;
+            task_log.borrow_mut().push((time::now() - start).as_secs());
+            time::sleep(Duration::from_secs(2)).await;
+        }
+        // Reset starts a period from now
+        interval.reset();
+        interval.tick().await;
+        task_log.borrow_mut().push((time::now() - start).as_secs());
+    });
+    executor.block();
+    assert_eq!(log.take(), [5, 10, 15, 20, 27]);
+}
@@ -0,0 +1,83 @@
+use runtime::io::{AsyncRead, AsyncReadExt};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    io,
+    pin::{Pin, pin},
+    task::{Context, Poll, Waker},
+};
+
+/// Returns the scripted results one read after the other, then end of file
+struct Script(VecDeque<Poll<io::Result<&'static [u8]>>>);
+
+impl AsyncRead for Script {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.0.pop_front() {
+            Some(Poll::Ready(Ok(bytes))) => {
+                let n = bytes.len().min(buf.len());
+                buf[..n].copy_from_slice(&bytes[..n]);
+                if n < bytes.len() {
+                    self.0.push_front(Poll::Ready(Ok(&bytes[n..])));
+                }
+                Poll::Ready(Ok(n))
+            }
+            Some(Poll::Ready(Err(e))) => Poll::Ready(Err(e)),
+            Some(Poll::Pending) => Poll::Pending,
+            None => Poll::Ready(Ok(0)),
+        }
+    }
+}
+
+fn poll<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+    future.poll(&mut Context::from_waker(Waker::noop()))
+}
+
+#[test]
+fn read_to_end_appends_everything() {
+    const LONG: [u8; 100] = [7; 100];
+    let mut reader = Script(VecDeque::from([
+        Poll::Ready(Ok(&b"abc"[..])),
+        Poll::Pending,
+        Poll::Ready(Ok(&LONG[..])),
+    ]));
+    let mut buf = b"start ".to_vec();
+    {
+        let mut read = pin!(reader.read_to_end(&mut buf));
+        assert!(poll(read.as_mut()).is_pending());
+        assert_eq!(poll(read.as_mut()).map(Result::ok), Poll::Ready(Some(103)));
+    }
+    assert_eq!(buf[..9], *b"start abc");
+    assert_eq!(buf[9..], LONG);
+}
+
+#[test]
+fn dropping_read_to_end_keeps_only_what_was_read() {
+    let mut reader = Script(VecDeque::from([
+        Poll::Ready(Ok(&b"abc"[..])),
+        Poll::Pending,
+    ]));
+    let mut buf = b"start ".to_vec();
+    {
+        let mut read = pin!(reader.read_to_end(&mut buf));
+        assert!(poll(read.as_mut()).is_pending());
+    }
+    assert_eq!(buf, b"start abc");
+}
+
+#[test]
+fn failed_read_to_end_keeps_only_what_was_read() {
+    let mut reader = Script(VecDeque::from([
+        Poll::Ready(Ok(&b"abc"[..])),
+        Poll::Ready(Err(io::ErrorKind::ConnectionReset.into())),
+    ]));
+    let mut buf = Vec::new();
+    let Poll::Ready(Err(e)) = poll(pin!(reader.read_to_end(&mut buf))) else {
+        panic!("the read should fail");
+    };
+    assert_eq!(e.kind(), io::ErrorKind::ConnectionReset);
+    assert_eq!(buf, b"abc");
+}
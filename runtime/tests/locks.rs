@@ -0,0 +1,131 @@
+mod common;
+
+use common::block_on;
+use runtime::{
+    spawn,
+    sync::{Mutex, RwLock},
+    task::yield_now,
+};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+#[test]
+fn mutex_is_handed_out_in_the_order_it_was_asked_for() {
+    let log = Log::default();
+    let tasks_log = log.clone();
+    block_on(async move {
+        let mutex = Arc::new(Mutex::new(Vec::new()));
+        let guard = mutex.lock().await;
+        for name in ["a", "b", "c"] {
+            let mutex = mutex.clone();
+            let log = tasks_log.clone();
+            spawn(async move {
+                let mut values = mutex.lock().await;
+                values.push(name);
+                log.borrow_mut().push(format!("{name} locked"));
+                // Holding the lock across an await lets the others queue up
+                yield_now().await;
+            });
+        }
+        // Let the spawned tasks run until they wait for the lock
+        for _ in 0..3 {
+            yield_now().await;
+        }
+        // Somebody waits, so the lock isn't free for a newcomer either
+        drop(guard);
+        assert!(mutex.try_lock().is_none());
+        let values = mutex.lock().await;
+        assert_eq!(*values, ["a", "b", "c"]);
+    });
+    assert_eq!(log.take(), ["a locked", "b locked", "c locked"]);
+}
+
+#[test]
+fn waiting_writer_goes_before_later_readers() {
+    let log = Log::default();
+    let tasks_log = log.clone();
+    block_on(async move {
+        let lock = Arc::new(RwLock::new(0));
+        let first_reader = lock.clone().read_owned().await;
+        let spawn_task = |name: &'static str, write: bool| {
+            let lock = lock.clone();
+            let log = tasks_log.clone();
+            spawn(async move {
+                if write {
+                    *lock.write().await += 1;
+                } else {
+                    let value = *lock.read().await;
+                    log.borrow_mut().push(format!("{name} read {value}"));
+                    return;
+                }
+                log.borrow_mut().push(format!("{name} wrote"));
+            });
+        };
+        spawn_task("writer", true);
+        yield_now().await;
+        // The writer waits for the first reader, the second one queues behind it
+        spawn_task("reader", false);
+        yield_now().await;
+        assert!(tasks_log.borrow().is_empty());
+        assert!(lock.try_read().is_none());
+        drop(first_reader);
+    });
+    assert_eq!(log.take(), ["writer wrote", "reader read 1"]);
+}
+
+#[test]
+fn readers_share_the_lock() {
+    block_on(async {
+        let lock = RwLock::new(5);
+        let a = lock.read().await;
+        let b = lock.read().await;
+        assert_eq!(*a + *b, 10);
+        assert!(lock.try_write().is_none());
+        drop((a, b));
+        *lock.try_write().unwrap() = 6;
+        assert_eq!(*lock.read().await, 6);
+    });
+}
+
+#[test]
+fn owned_guards_move_into_other_tasks() {
+    let mutex = Arc::new(Mutex::new(0));
+    let lock = Arc::new(RwLock::new(String::new()));
+    let (task_mutex, task_lock) = (mutex.clone(), lock.clone());
+    block_on(async move {
+        let mut counter = task_mutex.clone().lock_owned().await;
+        let mut text = task_lock.clone().write_owned().await;
+        spawn(async move {
+            yield_now().await;
+            *counter += 1;
+            text.push_str("written by another task");
+            // Dropping the guards here releases the locks
+        });
+        // Only free once the other task dropped the guards
+        assert_eq!(*task_mutex.lock().await, 1);
+        assert_eq!(*task_lock.read_owned().await, "written by another task");
+    });
+    assert_eq!(*mutex.try_lock().unwrap(), 1);
+    assert!(lock.try_write().is_some());
+}
+
+#[test]
+fn failed_try_lock_leaves_the_lock_taken() {
+    let mutex = Mutex::new(());
+    let guard = mutex.try_lock().unwrap();
+    for _ in 0..2 {
+        assert!(mutex.try_lock().is_none());
+    }
+    drop(guard);
+    assert!(mutex.try_lock().is_some());
+
+    let lock = RwLock::new(());
+    let reader = lock.try_read().unwrap();
+    assert!(lock.try_write().is_none());
+    drop(reader);
+    let writer = lock.try_write().unwrap();
+    assert!(lock.try_read().is_none());
+    assert!(lock.try_write().is_none());
+    drop(writer);
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::block_on;
+use runtime::{join, select, task::yield_now, try_join};
+use std::{
+    cell::{Cell, RefCell},
+    future::{pending, ready},
+    rc::Rc,
+};
+
+/// Ready after `yields` polls, records when it finished
+async fn after(yields: usize, value: u32, log: Rc<RefCell<Vec<u32>>>) -> u32 {
+    for _ in 0..yields {
+        yield_now().await;
+    }
+    log.borrow_mut().push(value);
+    value
+}
+
+/// Sets the flag when dropped, to see which futures were cancelled
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+#[test]
+fn select_disables_a_branch_whose_pattern_does_not_match() {
+    let picked = block_on(async {
+        select! {
+            biased;
+            Some(n) = ready(None::<u32>) => n,
+            n = async {
+                yield_now().await;
+                7
+            } => n + 1,
+        }
+    });
+    assert_eq!(picked, 8);
+}
+
+#[test]
+fn select_runs_else_once_all_branches_are_disabled() {
+    let picked = block_on(async {
+        let enabled = false;
+        select! {
+            Some(n) = ready(None::<u32>) => n,
+            Ok(n) = ready(Err::<u32, ()>(())) => n,
+            n = ready(3), if enabled => n,
+            else => 42,
+        }
+    });
+    assert_eq!(picked, 42);
+}
+
+#[test]
+fn select_binds_mut_patterns() {
+    let values = block_on(async {
+        select! {
+            (mut values, n) = ready((vec![1], 2)) => {
+                values.push(n);
+                values
+            }
+        }
+    });
+    assert_eq!(values, [1, 2]);
+}
+
+#[test]
+fn select_runs_default_if_nothing_is_ready() {
+    let picked = block_on(async {
+        select! {
+            n = pending::<u32>() => n,
+            default => 0,
+        }
+    });
+    assert_eq!(picked, 0);
+}
+
+#[test]
+fn biased_select_polls_branches_in_order() {
+    block_on(async {
+        for _ in 0..20 {
+            let picked = select! {
+                biased;
+                a = ready('a') => a,
+                b = ready('b') => b,
+            };
+            assert_eq!(picked, 'a');
+        }
+    });
+}
+
+#[test]
+fn select_drops_the_futures_that_lost() {
+    let dropped = Rc::new(Cell::new(false));
+    let flag = DropFlag(dropped.clone());
+    let check = dropped.clone();
+    block_on(async move {
+        select! {
+            _ = async move {
+                let _flag = flag;
+                pending::<()>().await
+            } => unreachable!(),
+            // Already dropped when the handler runs
+            _ = yield_now() => assert!(check.get()),
+        }
+    });
+    assert!(dropped.get());
+}
+
+#[test]
+fn select_handler_can_use_what_the_futures_borrowed() {
+    let values = block_on(async {
+        let mut values = vec![1];
+        select! {
+            _ = async {
+                values.push(2);
+                pending::<()>().await
+            } => unreachable!(),
+            _ = yield_now() => values.push(3),
+        }
+        values
+    });
+    assert_eq!(values, [1, 2, 3]);
+}
+
+#[test]
+fn join_waits_for_all_futures_and_keeps_their_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let finished = log.clone();
+    let outputs = block_on(async move {
+        join!(
+            after(3, 1, log.clone()),
+            after(1, 2, log.clone()),
+            after(2, 3, log),
+        )
+    });
+    assert_eq!(outputs, (1, 2, 3));
+    // They ran concurrently, the shortest one finished first
+    assert_eq!(finished.take(), [2, 3, 1]);
+}
+
+#[test]
+fn try_join_returns_all_outputs_on_success() {
+    let outputs = block_on(async {
+        try_join!(async { Ok::<_, &str>(1) }, async {
+            yield_now().await;
+            Ok("two")
+        })
+    });
+    assert_eq!(outputs, Ok((1, "two")));
+}
+
+#[test]
+fn try_join_fails_with_the_first_error_and_drops_the_rest() {
+    let dropped = Rc::new(Cell::new(false));
+    let flag = DropFlag(dropped.clone());
+    let outcome = block_on(async move {
+        try_join!(
+            async move {
+                let _flag = flag;
+                pending::<Result<u32, &str>>().await
+            },
+            async {
+                yield_now().await;
+                Err::<u32, _>("first")
+            },
+            async {
+                for _ in 0..3 {
+                    yield_now().await;
+                }
+                Err::<u32, _>("second")
+            },
+        )
+    });
+    assert_eq!(outcome, Err("first"));
+    assert!(dropped.get());
+}
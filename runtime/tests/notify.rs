@@ -0,0 +1,75 @@
+mod common;
+
+use common::block_on;
+use runtime::{spawn, sync::Notify, task::yield_now};
+use std::{
+    cell::Cell,
+    future::Future,
+    pin::pin,
+    rc::Rc,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+};
+
+fn is_ready(future: impl Future) -> bool {
+    let mut cx = Context::from_waker(Waker::noop());
+    pin!(future).poll(&mut cx).is_ready()
+}
+
+#[test]
+fn notify_one_stores_a_single_permit() {
+    let notify = Notify::new();
+    notify.notify_one();
+    notify.notify_one();
+    assert!(is_ready(notify.notified()));
+    assert!(!is_ready(notify.notified()));
+}
+
+#[test]
+fn notify_waiters_wakes_everyone_waiting_but_stores_nothing() {
+    block_on(async {
+        let notify = Arc::new(Notify::new());
+        let woken = Rc::new(Cell::new(0));
+        for _ in 0..3 {
+            let notify = notify.clone();
+            let woken = woken.clone();
+            spawn(async move {
+                notify.notified().await;
+                woken.set(woken.get() + 1);
+            });
+        }
+        for _ in 0..3 {
+            yield_now().await;
+        }
+        notify.notify_waiters();
+        for _ in 0..3 {
+            yield_now().await;
+        }
+        assert_eq!(woken.get(), 3);
+        assert!(!is_ready(notify.notified()));
+    });
+}
+
+#[test]
+fn notify_waiters_keeps_a_stored_permit() {
+    let notify = Notify::new();
+    let mut before_broadcast = pin!(notify.notified());
+    notify.notify_one();
+    notify.notify_waiters();
+    // Completed by the broadcast, the permit is left for the next waiter
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(before_broadcast.as_mut().poll(&mut cx), Poll::Ready(()));
+    assert!(is_ready(notify.notified()));
+    assert!(!is_ready(notify.notified()));
+}
+
+#[test]
+fn enabled_waiter_receives_notify_one_instead_of_a_permit() {
+    let notify = Notify::new();
+    let mut first = pin!(notify.notified());
+    first.as_mut().enable();
+    notify.notify_one();
+    let mut cx = Context::from_waker(Waker::noop());
+    assert_eq!(first.as_mut().poll(&mut cx), Poll::Ready(()));
+    assert!(!is_ready(notify.notified()));
+}
@@ -0,0 +1,99 @@
+use runtime::{
+    Executor,
+    task::{self, Builder, Dispatch, Priority},
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// Run tasks that each log their name `polls` times, yielding in between,
+/// and return the order they ran in
+fn run(mut executor: Executor, tasks: &[(char, Priority, usize)]) -> String {
+    let log = Rc::new(RefCell::new(String::new()));
+    for &(name, priority, polls) in tasks {
+        let log = log.clone();
+        Builder::new()
+            .priority(priority)
+            .schedule(&mut executor, async move {
+                for poll in 0..polls {
+                    if poll > 0 {
+                        task::yield_now().await;
+                    }
+                    log.borrow_mut().push(name);
+                }
+            });
+    }
+    executor.block();
+    log.take()
+}
+
+#[test]
+fn tasks_of_one_priority_run_in_fifo_order() {
+    let tasks = [
+        ('a', Priority::Normal, 3),
+        ('b', Priority::Normal, 3),
+        ('c', Priority::Normal, 3),
+    ];
+    assert_eq!(run(Executor::new(), &tasks), "abcabcabc");
+}
+
+#[test]
+fn strict_dispatch_runs_the_highest_priority_first() {
+    let tasks = [
+        ('l', Priority::Low, 4),
+        ('n', Priority::Normal, 4),
+        ('h', Priority::High, 4),
+    ];
+    assert_eq!(run(Executor::new(), &tasks), "hhhhnnnnllll");
+}
+
+#[test]
+fn aging_runs_a_passed_over_task() {
+    let tasks = [('h', Priority::High, 12), ('l', Priority::Low, 3)];
+    let executor = Executor::new().aging(3);
+    assert_eq!(run(executor, &tasks), "hhhlhhhlhhhlhhh");
+}
+
+#[test]
+fn weighted_dispatch_shares_turns_by_weight() {
+    let weighted = Dispatch::Weighted {
+        high: 3,
+        normal: 2,
+        low: 1,
+    };
+    let tasks = [
+        ('l', Priority::Low, 6),
+        ('n', Priority::Normal, 6),
+        ('h', Priority::High, 6),
+    ];
+    let executor = Executor::new().dispatch(weighted);
+    // Once a priority has no ready tasks, the others share its turns
+    assert_eq!(run(executor, &tasks), "hhhnnlhhhnnlnnllll");
+}
+
+#[test]
+fn weighted_dispatch_keeps_the_ratio_while_all_are_busy() {
+    let weighted = Dispatch::Weighted {
+        high: 3,
+        normal: 2,
+        low: 1,
+    };
+    let tasks = [
+        ('h', Priority::High, 100),
+        ('n', Priority::Normal, 100),
+        ('l', Priority::Low, 100),
+    ];
+    let executor = Executor::new().dispatch(weighted).aging(100);
+    let order = run(executor, &tasks);
+    let first: String = order.chars().take(120).collect();
+    let count = |name| first.chars().filter(|c| *c == name).count();
+    assert_eq!((count('h'), count('n'), count('l')), (60, 40, 20));
+}
+
+#[test]
+#[should_panic(expected = "dispatch weights must be at least 1")]
+fn zero_weights_are_rejected() {
+    let _ = Executor::new().dispatch(Dispatch::Weighted {
+        high: 1,
+        normal: 0,
+        low: 1,
+    });
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use common::block_on;
+use runtime::{spawn, sync::Semaphore, task::yield_now};
+use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+#[test]
+fn big_acquisition_is_not_overtaken_by_small_ones() {
+    let order = block_on(async {
+        let semaphore = Arc::new(Semaphore::new(3));
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let held = semaphore.clone().acquire_owned().await.unwrap();
+        for (name, permits) in [("big", 3), ("small", 1)] {
+            let semaphore = semaphore.clone();
+            let order = order.clone();
+            spawn(async move {
+                let _permit = semaphore.acquire_many(permits).await.unwrap();
+                order.borrow_mut().push(name);
+            });
+        }
+        yield_now().await;
+        // Two permits are free, but the small waiter queues behind the big one
+        assert_eq!(semaphore.available_permits(), 2);
+        assert!(order.borrow().is_empty());
+        drop(held);
+        while order.borrow().len() < 2 {
+            yield_now().await;
+        }
+        assert_eq!(semaphore.available_permits(), 3);
+        order.take()
+    });
+    assert_eq!(order, ["big", "small"]);
+}
+
+#[test]
+fn close_fails_waiters_but_keeps_permits_valid() {
+    block_on(async {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let waiter = semaphore.clone();
+        let failed = Rc::new(RefCell::new(None));
+        let result = failed.clone();
+        spawn(async move {
+            *result.borrow_mut() = Some(waiter.acquire().await.is_err());
+        });
+        yield_now().await;
+        semaphore.close();
+        yield_now().await;
+        assert_eq!(failed.take(), Some(true));
+        assert_eq!(permit.num_permits(), 1);
+        drop(permit);
+        assert!(semaphore.try_acquire().is_err());
+    });
+}
+
+#[test]
+fn added_permits_serve_waiters_first() {
+    block_on(async {
+        let semaphore = Arc::new(Semaphore::new(0));
+        let waiter = semaphore.clone();
+        spawn(async move {
+            waiter.acquire_many(2).await.unwrap().forget();
+        });
+        yield_now().await;
+        semaphore.add_permits(2);
+        yield_now().await;
+        assert_eq!(semaphore.available_permits(), 0);
+    });
+}
+
+#[test]
+fn an_empty_semaphore_waits_for_added_permits() {
+    block_on(async {
+        let semaphore = Arc::new(Semaphore::new(0));
+        let signal = semaphore.clone();
+        spawn(async move {
+            yield_now().await;
+            signal.add_permits(1);
+        });
+        semaphore.acquire().await.unwrap().forget();
+    });
+}
+
+#[test]
+fn acquiring_more_than_the_semaphore_holds_waits() {
+    block_on(async {
+        let semaphore = Arc::new(Semaphore::new(2));
+        // Forgetting a permit shrinks the semaphore to one
+        semaphore.acquire().await.unwrap().forget();
+        let waiter = semaphore.clone();
+        let acquired = Rc::new(RefCell::new(false));
+        let done = acquired.clone();
+        spawn(async move {
+            let permit = waiter.acquire_many(3).await.unwrap();
+            assert_eq!(permit.num_permits(), 3);
+            *done.borrow_mut() = true;
+        });
+        yield_now().await;
+        semaphore.add_permits(1);
+        yield_now().await;
+        assert!(!*acquired.borrow());
+        semaphore.add_permits(1);
+        for _ in 0..3 {
+            yield_now().await;
+        }
+        assert!(*acquired.borrow());
+        assert_eq!(semaphore.available_permits(), 3);
+    });
+}
+
+#[test]
+fn acquiring_more_than_max_permits_fails() {
+    block_on(async {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let err = semaphore
+            .acquire_many(Semaphore::MAX_PERMITS + 1)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("more permits"));
+        assert!(
+            semaphore
+                .clone()
+                .acquire_many_owned(usize::MAX)
+                .await
+                .is_err()
+        );
+        // The semaphore is still usable
+        assert_eq!(semaphore.acquire().await.unwrap().num_permits(), 1);
+    });
+}
+
+#[test]
+#[should_panic(expected = "can't hold more than")]
+fn adding_past_max_permits_panics() {
+    let semaphore = Semaphore::new(Semaphore::MAX_PERMITS);
+    semaphore.add_permits(1);
+}
+
+#[test]
+#[should_panic(expected = "can't hold more than")]
+fn adding_an_overflowing_amount_panics() {
+    let semaphore = Semaphore::new(1);
+    semaphore.add_permits(usize::MAX);
+}
@@ -0,0 +1,152 @@
+use async_timer::AsyncTimer;
+use runtime::{Executor, future::race, task::yield_now, time};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// The timers of the demo binary: 9 ticks of a 5 second interval, next to a
+/// 5 and a 1 second timer. Logs the virtual second each timer fired at.
+fn schedule_demo(executor: &mut Executor, log: &Log) {
+    let start = time::now();
+    let at = move || (time::now() - start).as_secs();
+    for secs in [5, 1] {
+        let log = log.clone();
+        executor.schedule(async move {
+            AsyncTimer::new(Duration::from_secs(secs)).await;
+            log.borrow_mut().push(format!("{secs}s timer at {}", at()));
+        });
+    }
+    let log = log.clone();
+    executor.schedule(async move {
+        let period = Duration::from_secs(5);
+        let mut interval = time::interval_at(time::now() + period, period);
+        for tick in 1..10 {
+            interval.tick().await;
+            log.borrow_mut().push(format!("tick {tick} at {}", at()));
+        }
+    });
+}
+
+/// Tasks of the same priority that race each other, logs who ran when
+fn run_contention(seed: u64) -> Vec<String> {
+    let log = Log::default();
+    let mut executor = Executor::simulated(seed);
+    for name in ["a", "b", "c", "d"] {
+        let log = log.clone();
+        executor.schedule(async move {
+            for round in 0..3 {
+                let winner = race(
+                    async {
+                        time::sleep(Duration::from_millis(10)).await;
+                        "sleep"
+                    },
+                    async {
+                        time::sleep(Duration::from_millis(10)).await;
+                        "other sleep"
+                    },
+                )
+                .await;
+                log.borrow_mut().push(format!("{name}{round} {winner}"));
+                yield_now().await;
+            }
+        });
+    }
+    executor.block();
+    log.take()
+}
+
+#[test]
+fn the_demo_runs_45_virtual_seconds_in_no_time() {
+    let wall = Instant::now();
+    let log = Log::default();
+    let mut executor = Executor::simulated(0);
+    let start = time::now();
+    schedule_demo(&mut executor, &log);
+    executor.block();
+    assert_eq!(time::now() - start, Duration::from_secs(45));
+    assert!(
+        wall.elapsed() < Duration::from_secs(1),
+        "{:?}",
+        wall.elapsed()
+    );
+    let log = log.take();
+    let ticks: Vec<_> = log
+        .iter()
+        .filter(|line| line.starts_with("tick"))
+        .cloned()
+        .collect();
+    let expected: Vec<_> = (1..10)
+        .map(|tick| format!("tick {tick} at {}", tick * 5))
+        .collect();
+    assert_eq!(ticks, expected);
+    assert!(log.contains(&"1s timer at 1".to_string()));
+    assert!(log.contains(&"5s timer at 5".to_string()));
+}
+
+#[test]
+fn the_same_seed_repeats_the_same_order() {
+    let first = run_contention(42);
+    assert_eq!(first.len(), 12);
+    assert_eq!(run_contention(42), first);
+    // Other seeds explore other orders
+    assert!((0..10).any(|seed| run_contention(seed) != first));
+}
+
+#[test]
+fn advance_fires_the_timers_that_are_due() {
+    let fired = Rc::new(RefCell::new(Vec::new()));
+    let mut executor = Executor::simulated(0);
+    for secs in [10, 20] {
+        let fired = fired.clone();
+        executor.schedule(async move {
+            time::sleep(Duration::from_secs(secs)).await;
+            fired.borrow_mut().push(secs);
+        });
+    }
+    let check = fired.clone();
+    executor.schedule(async move {
+        yield_now().await;
+        let start = time::now();
+        time::advance(Duration::from_secs(15));
+        assert_eq!(time::now() - start, Duration::from_secs(15));
+        yield_now().await;
+        yield_now().await;
+        assert_eq!(*check.borrow(), [10]);
+    });
+    executor.block();
+    assert_eq!(fired.take(), [10, 20]);
+}
+
+#[test]
+fn dropping_the_simulated_executor_resumes_real_time() {
+    let mut executor = Executor::simulated(0);
+    executor.schedule(async {
+        time::sleep(Duration::from_secs(60)).await;
+    });
+    executor.block();
+    drop(executor);
+
+    let wall = Instant::now();
+    // The paused clock would be a minute ahead
+    assert!(time::now() - wall < Duration::from_secs(1));
+    let mut executor = Executor::new();
+    executor.schedule(async {
+        AsyncTimer::new(Duration::from_millis(20)).await;
+        time::sleep(Duration::from_millis(20)).await;
+    });
+    executor.block();
+    assert!(wall.elapsed() >= Duration::from_millis(40));
+}
+
+#[test]
+fn a_clock_paused_before_stays_paused() {
+    time::pause();
+    drop(Executor::simulated(0));
+    let start = time::now();
+    time::advance(Duration::from_secs(1));
+    assert_eq!(time::now() - start, Duration::from_secs(1));
+}
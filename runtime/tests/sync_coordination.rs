@@ -0,0 +1,95 @@
+mod common;
+
+use async_timer::AsyncTimer;
+use common::block_on;
+use runtime::{
+    spawn,
+    sync::{Barrier, CountDownLatch, WaitGroup},
+};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+fn millis(ms: u64) -> AsyncTimer {
+    AsyncTimer::new(Duration::from_millis(ms))
+}
+
+#[test]
+fn barrier_releases_all_tasks_and_elects_one_leader_per_phase() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let tasks_log = log.clone();
+    block_on(async move {
+        let barrier = Rc::new(Barrier::new(3));
+        for (task, delay) in [(0, 30), (1, 10), (2, 20)] {
+            let barrier = barrier.clone();
+            let log = tasks_log.clone();
+            spawn(async move {
+                for phase in 0..2 {
+                    // The timers make the tasks arrive in a different order
+                    millis(delay * (phase + 1)).await;
+                    let leader = barrier.wait().await.is_leader();
+                    log.borrow_mut().push((phase, task, leader));
+                }
+            });
+        }
+    });
+    // `block_on` only returns once the spawned tasks are done
+    let log = log.take();
+    assert_eq!(log.len(), 6);
+    for phase in 0..2 {
+        let passed: Vec<_> = log.iter().filter(|entry| entry.0 == phase).collect();
+        assert_eq!(passed.len(), 3);
+        assert_eq!(passed.iter().filter(|entry| entry.2).count(), 1);
+    }
+    // Nobody starts the second phase before everyone passed the first
+    let first_phase_done = log.iter().rposition(|entry| entry.0 == 0).unwrap();
+    assert!(log[..=first_phase_done].iter().all(|entry| entry.0 == 0));
+    // Task 0 has the longest timer, so it arrives last in both phases
+    assert!(log.contains(&(0, 0, true)));
+    assert!(log.contains(&(1, 0, true)));
+}
+
+#[test]
+fn latch_opens_once_every_timer_counted_down() {
+    block_on(async {
+        let latch = Rc::new(CountDownLatch::new(3));
+        let fired = Rc::new(RefCell::new(0));
+        for delay in [10, 20, 30] {
+            let latch = latch.clone();
+            let fired = fired.clone();
+            spawn(async move {
+                millis(delay).await;
+                *fired.borrow_mut() += 1;
+                latch.count_down();
+            });
+        }
+        latch.wait().await;
+        assert_eq!(*fired.borrow(), 3);
+        assert_eq!(latch.count(), 0);
+        // Once open the latch stays open
+        latch.count_down();
+        latch.wait().await;
+    });
+}
+
+#[test]
+fn wait_group_waits_for_all_tasks_and_can_be_reused() {
+    block_on(async {
+        let group = Rc::new(WaitGroup::new());
+        let finished = Rc::new(RefCell::new(Vec::new()));
+        for round in 0..2 {
+            for delay in [20, 10] {
+                group.add(1);
+                let group = group.clone();
+                let finished = finished.clone();
+                spawn(async move {
+                    millis(delay).await;
+                    finished.borrow_mut().push((round, delay));
+                    group.done();
+                });
+            }
+            group.wait().await;
+            assert_eq!(group.count(), 0);
+            assert_eq!(finished.borrow().len(), 2 * (round + 1));
+        }
+        assert_eq!(finished.take(), [(0, 10), (0, 20), (1, 10), (1, 20)]);
+    });
+}
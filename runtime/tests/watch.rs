@@ -0,0 +1,73 @@
+mod common;
+
+use common::block_on;
+use runtime::{spawn, sync::watch, task::yield_now};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn changed_coalesces_several_sends() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    block_on(async move {
+        let (tx, mut rx) = watch::channel(0);
+        spawn(async move {
+            while rx.changed().await.is_ok() {
+                log.borrow_mut().push(*rx.borrow());
+            }
+        });
+        yield_now().await;
+        // The receiver only runs after all three sends
+        for n in 1..=3 {
+            tx.send(n).unwrap();
+        }
+        yield_now().await;
+        yield_now().await;
+        tx.send(4).unwrap();
+    });
+    assert_eq!(seen.take(), [3, 4]);
+}
+
+#[test]
+fn changed_fails_once_the_sender_is_dropped() {
+    block_on(async {
+        let (tx, mut rx) = watch::channel("a");
+        tx.send("b").unwrap();
+        drop(tx);
+        // A change sent before the drop is still reported
+        assert!(rx.changed().await.is_ok());
+        assert_eq!(*rx.borrow(), "b");
+        assert!(rx.changed().await.is_err());
+        assert!(rx.has_changed().is_err());
+    });
+}
+
+#[test]
+fn borrow_and_update_marks_the_value_as_seen() {
+    let (tx, mut rx) = watch::channel(1);
+    assert_eq!(rx.has_changed(), Ok(false));
+    tx.send(2).unwrap();
+    assert_eq!(*rx.borrow(), 2);
+    // `borrow` only looks
+    assert_eq!(rx.has_changed(), Ok(true));
+    assert_eq!(*rx.borrow_and_update(), 2);
+    assert_eq!(rx.has_changed(), Ok(false));
+    tx.send_modify(|value| *value += 1);
+    assert_eq!(rx.has_changed(), Ok(true));
+    rx.mark_unchanged();
+    assert_eq!(rx.has_changed(), Ok(false));
+}
+
+#[test]
+fn new_subscribers_have_seen_the_current_value() {
+    let (tx, rx) = watch::channel(1);
+    tx.send(2).unwrap();
+    let subscribed = tx.subscribe();
+    assert_eq!(subscribed.has_changed(), Ok(false));
+    assert_eq!(*subscribed.borrow(), 2);
+    assert_eq!(rx.has_changed(), Ok(true));
+    drop((rx, subscribed));
+    assert!(tx.is_closed());
+    assert!(tx.send(3).is_err());
+    assert_eq!(tx.send_replace(4), 2);
+    assert_eq!(*tx.borrow(), 4);
+}
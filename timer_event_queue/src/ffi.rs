@@ -1,13 +1,34 @@
 pub const EPOLL_CTL_ADD: i32 = 1;
+pub const EPOLL_CTL_DEL: i32 = 2;
+pub const EPOLL_CTL_MOD: i32 = 3;
 pub const EPOLL_IN: i32 = 0x1;
+pub const EPOLLOUT: i32 = 0x4;
+pub const EPOLLERR: i32 = 0x8;
+pub const EPOLLHUP: i32 = 0x10;
+pub const EPOLLRDHUP: i32 = 0x2000;
+pub const EPOLLONESHOT: i32 = 1 << 30;
 pub const EPOLLET: i32 = 1 << 31;
 
+pub const POLLIN: i16 = 0x1;
+pub const POLLOUT: i16 = 0x4;
+pub const POLLERR: i16 = 0x8;
+pub const POLLHUP: i16 = 0x10;
+pub const POLLNVAL: i16 = 0x20;
+pub const POLLRDHUP: i16 = 0x2000;
+
+pub const O_NONBLOCK: i32 = 0o4000;
+pub const O_CLOEXEC: i32 = 0o2000000;
+
 #[link(name = "c")]
 unsafe extern "C" {
     pub fn epoll_create(size: i32) -> i32;
     pub fn close(fd: i32) -> i32;
     pub fn epoll_ctl(epfd: i32, op: i32, fd: i32, event: *mut Event) -> i32;
     pub fn epoll_wait(epfd: i32, events: *mut Event, maxevents: i32, timeout: i32) -> i32;
+    pub fn poll(fds: *mut PollFd, nfds: u64, timeout: i32) -> i32;
+    pub fn pipe2(fds: *mut i32, flags: i32) -> i32;
+    pub fn read(fd: i32, buf: *mut u8, count: usize) -> isize;
+    pub fn write(fd: i32, buf: *const u8, count: usize) -> isize;
 }
 
 #[derive(Debug)]
@@ -21,4 +42,17 @@ impl Event {
     pub fn token(&self) -> usize {
         self.epoll_data
     }
+    /// The readiness flags (`EPOLL_IN`, `EPOLLOUT`, ...) that were reported
+    pub fn events(&self) -> u32 {
+        self.events
+    }
+}
+
+/// `struct pollfd` as expected by `poll(2)`
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct PollFd {
+    pub(crate) fd: i32,
+    pub(crate) events: i16,
+    pub(crate) revents: i16,
 }
@@ -0,0 +1,3 @@
+pub mod ffi;
+pub mod poll;
+pub mod selector;
@@ -1,12 +1,11 @@
-mod ffi;
-mod poll;
-use ffi::Event;
-use poll::Poll;
 use std::{
-    fmt::format,
     io::{self, Read, Result, Write},
     net::TcpStream,
 };
+use timer_event_queue::{
+    ffi::{self, Event},
+    poll::Poll,
+};
 
 fn get_req(path: &str) -> Vec<u8> {
     format!(
@@ -57,7 +56,7 @@ fn handle_events(events: &[Event], streams: &mut [TcpStream]) -> Result<usize> {
         let mut data = vec![0u8; 4096];
         loop {
             match streams[index].read(&mut data) {
-                Ok(n) if n == 0 => {
+                Ok(0) => {
                     handled_events += 1;
                     break;
                 }
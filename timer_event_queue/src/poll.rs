@@ -1,68 +1,85 @@
-use crate::ffi;
+use crate::{ffi, selector::EpollSelector};
 use std::{
-    io::{self, Result},
-    net::TcpStream,
-    os::fd::AsRawFd,
+    io::Result,
+    os::fd::{AsRawFd, RawFd},
+    sync::Arc,
 };
-type Events = Vec<ffi::Event>;
-pub struct Poll {
-    registry: Registry,
+pub type Events = Vec<ffi::Event>;
+
+/// A readiness mechanism that `Poll` can block on.
+///
+/// Interests and reported events use the epoll flags from `ffi`
+/// (`EPOLL_IN`, `EPOLLOUT`, `EPOLLET`, `EPOLLONESHOT`, ...), backends that
+/// are not built on epoll translate them to whatever they use internally.
+pub trait Selector {
+    /// Start watching `fd` and report its events with `token`
+    fn register(&self, fd: RawFd, token: usize, interests: i32) -> Result<()>;
+    /// Change the token/interests of an already registered `fd`
+    /// (this also re-arms a `EPOLLONESHOT` registration)
+    fn reregister(&self, fd: RawFd, token: usize, interests: i32) -> Result<()>;
+    /// Stop watching `fd`
+    fn deregister(&self, fd: RawFd) -> Result<()>;
+    /// Block until at least one event is ready or the timeout (in ms) expires.
+    /// At most `events.capacity()` events are written into `events`.
+    fn select(&self, events: &mut Events, timeout: Option<i32>) -> Result<()>;
+}
+
+pub struct Poll<S: Selector = EpollSelector> {
+    registry: Registry<S>,
 }
 impl Poll {
-    /// Create a new event queue
+    /// Create a new event queue backed by epoll
     pub fn new() -> Result<Self> {
-        let res = unsafe { ffi::epoll_create(1) };
-        if res < 0 {
-            return Err(io::Error::last_os_error());
+        Ok(Self::with_selector(EpollSelector::new()?))
+    }
+}
+impl<S: Selector> Poll<S> {
+    /// Create a new event queue backed by the given selector
+    pub fn with_selector(selector: S) -> Self {
+        Self {
+            registry: Registry {
+                selector: Arc::new(selector),
+            },
         }
-        Ok(Self {
-            registry: Registry { raw_fd: res },
-        })
     }
     /// Register interest for event notifications using the registry
-    pub fn registry(&self) -> &Registry {
+    pub fn registry(&self) -> &Registry<S> {
         &self.registry
     }
     /// Block the thread until an event is ready or it times out
     pub fn poll(&mut self, events: &mut Events, timeout: Option<i32>) -> Result<()> {
-        let fd = self.registry.raw_fd;
-        let timeout = timeout.unwrap_or(-1);
-        let max_events = events.capacity() as i32;
-        let res = unsafe { ffi::epoll_wait(fd, events.as_mut_ptr(), max_events, timeout) };
-        if res < 0 {
-            return Err(io::Error::last_os_error());
-        }
-        unsafe { events.set_len(res as usize) };
-        Ok(())
+        self.registry.selector.select(events, timeout)
     }
 }
-pub struct Registry {
-    raw_fd: i32,
+/// Handle to register event sources with a `Poll`.
+/// It can be cloned and sent to other threads while the `Poll` is blocked.
+pub struct Registry<S: Selector = EpollSelector> {
+    selector: Arc<S>,
 }
-impl Registry {
-    /// Register interest for an event notification
-    /// (for simplicity only Tcp events are considered,
-    ///  but we could extend this with an abstraction over
-    ///  different event sources)
-    pub fn register(&self, source: &TcpStream, token: usize, interests: i32) -> Result<()> {
-        let mut event = ffi::Event {
-            events: interests as u32,
-            epoll_data: token,
-        };
-        let op = ffi::EPOLL_CTL_ADD;
-        let res = unsafe { ffi::epoll_ctl(self.raw_fd, op, source.as_raw_fd(), &mut event) };
-        if res < 0 {
-            return Err(io::Error::last_os_error());
+impl<S: Selector> Clone for Registry<S> {
+    fn clone(&self) -> Self {
+        Self {
+            selector: self.selector.clone(),
         }
-        Ok(())
     }
 }
-impl Drop for Registry {
-    fn drop(&mut self) {
-        let res = unsafe { ffi::close(self.raw_fd) };
-        if res < 0 {
-            let err = io::Error::last_os_error();
-            eprintln!("ERROR: {err:?}");
-        }
+impl<S: Selector> Registry<S> {
+    /// Register interest for an event notification on any source
+    /// that is backed by a file descriptor (sockets, pipes, eventfds, ...)
+    pub fn register(&self, source: &impl AsRawFd, token: usize, interests: i32) -> Result<()> {
+        self.selector.register(source.as_raw_fd(), token, interests)
+    }
+    /// Change the interests of an already registered source
+    pub fn reregister(&self, source: &impl AsRawFd, token: usize, interests: i32) -> Result<()> {
+        self.selector
+            .reregister(source.as_raw_fd(), token, interests)
+    }
+    /// Remove a source from the event queue
+    pub fn deregister(&self, source: &impl AsRawFd) -> Result<()> {
+        self.selector.deregister(source.as_raw_fd())
+    }
+    /// Access the underlying selector, e.g. to inject events into a `MockSelector`
+    pub fn selector(&self) -> &S {
+        &self.selector
     }
 }
@@ -0,0 +1,13 @@
+//! Backends that `Poll` can use to wait for readiness events.
+//!
+//! - `EpollSelector`: the Linux epoll event queue (the default)
+//! - `PollSelector`: a portable, level-triggered fallback built on `poll(2)`
+//! - `MockSelector`: an in-memory event queue without any real file descriptors,
+//!   readiness is injected by hand which makes it usable in tests and under Miri
+mod epoll;
+mod mock;
+mod poll;
+
+pub use epoll::EpollSelector;
+pub use mock::MockSelector;
+pub use poll::PollSelector;
@@ -0,0 +1,60 @@
+use crate::{ffi, poll::Events, poll::Selector};
+use std::{
+    io::{self, Result},
+    os::fd::RawFd,
+};
+
+pub struct EpollSelector {
+    raw_fd: i32,
+}
+impl EpollSelector {
+    pub fn new() -> Result<Self> {
+        let res = unsafe { ffi::epoll_create(1) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { raw_fd: res })
+    }
+
+    fn ctl(&self, op: i32, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        let mut event = ffi::Event {
+            events: interests as u32,
+            epoll_data: token,
+        };
+        let res = unsafe { ffi::epoll_ctl(self.raw_fd, op, fd, &mut event) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+impl Selector for EpollSelector {
+    fn register(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        self.ctl(ffi::EPOLL_CTL_ADD, fd, token, interests)
+    }
+    fn reregister(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        self.ctl(ffi::EPOLL_CTL_MOD, fd, token, interests)
+    }
+    fn deregister(&self, fd: RawFd) -> Result<()> {
+        self.ctl(ffi::EPOLL_CTL_DEL, fd, 0, 0)
+    }
+    fn select(&self, events: &mut Events, timeout: Option<i32>) -> Result<()> {
+        let timeout = timeout.unwrap_or(-1);
+        let max_events = events.capacity() as i32;
+        let res = unsafe { ffi::epoll_wait(self.raw_fd, events.as_mut_ptr(), max_events, timeout) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        unsafe { events.set_len(res as usize) };
+        Ok(())
+    }
+}
+impl Drop for EpollSelector {
+    fn drop(&mut self) {
+        let res = unsafe { ffi::close(self.raw_fd) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            eprintln!("ERROR: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{ffi, poll::Events, poll::Selector};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Result},
+    os::fd::RawFd,
+    sync::{Condvar, Mutex},
+    time::Duration,
+};
+
+struct Registration {
+    token: usize,
+    interests: i32,
+    armed: bool,
+}
+
+#[derive(Default)]
+struct State {
+    registrations: HashMap<RawFd, Registration>,
+    // Readiness injected with `set_ready` that was not delivered yet
+    pending: VecDeque<(RawFd, u32)>,
+}
+
+/// In-memory selector that never touches the OS.
+///
+/// "File descriptors" are plain numbers, readiness is injected with
+/// `set_ready` (from any thread) and delivered once by the next `select`,
+/// like an edge-triggered epoll would do. `EPOLLONESHOT` registrations are
+/// disarmed after delivery until they are re-registered.
+#[derive(Default)]
+pub struct MockSelector {
+    state: Mutex<State>,
+    ready: Condvar,
+}
+impl MockSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark `fd` as ready with the given epoll flags (`EPOLL_IN`, `EPOLLOUT`, ...)
+    pub fn set_ready(&self, fd: RawFd, readiness: i32) {
+        let mut state = self.state.lock().unwrap();
+        match state
+            .pending
+            .iter_mut()
+            .find(|(pending_fd, _)| *pending_fd == fd)
+        {
+            Some((_, pending)) => *pending |= readiness as u32,
+            None => state.pending.push_back((fd, readiness as u32)),
+        }
+        self.ready.notify_all();
+    }
+
+    /// Whether `fd` is currently registered (and armed)
+    pub fn is_registered(&self, fd: RawFd) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .registrations
+            .get(&fd)
+            .is_some_and(|registration| registration.armed)
+    }
+
+    fn deliver(state: &mut State, events: &mut Events) {
+        let mut undelivered = VecDeque::new();
+        while let Some((fd, readiness)) = state.pending.pop_front() {
+            if events.len() == events.capacity() {
+                undelivered.push_back((fd, readiness));
+                continue;
+            }
+            let Some(registration) = state.registrations.get_mut(&fd) else {
+                continue;
+            };
+            // Errors and hang ups are always reported, like epoll does
+            let always = (ffi::EPOLLERR | ffi::EPOLLHUP) as u32;
+            let readiness = readiness & (registration.interests as u32 | always);
+            if !registration.armed || readiness == 0 {
+                continue;
+            }
+            events.push(ffi::Event {
+                events: readiness,
+                epoll_data: registration.token,
+            });
+            if registration.interests & ffi::EPOLLONESHOT != 0 {
+                registration.armed = false;
+            }
+        }
+        state.pending = undelivered;
+    }
+}
+impl Selector for MockSelector {
+    fn register(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.registrations.contains_key(&fd) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        state.registrations.insert(
+            fd,
+            Registration {
+                token,
+                interests,
+                armed: true,
+            },
+        );
+        Ok(())
+    }
+    fn reregister(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let registration = state
+            .registrations
+            .get_mut(&fd)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        registration.token = token;
+        registration.interests = interests;
+        registration.armed = true;
+        Ok(())
+    }
+    fn deregister(&self, fd: RawFd) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .registrations
+            .remove(&fd)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        state.pending.retain(|(pending_fd, _)| *pending_fd != fd);
+        Ok(())
+    }
+    fn select(&self, events: &mut Events, timeout: Option<i32>) -> Result<()> {
+        events.clear();
+        let mut state = self.state.lock().unwrap();
+        Self::deliver(&mut state, events);
+        if events.is_empty() && timeout != Some(0) {
+            state = match timeout {
+                Some(ms) if ms > 0 => {
+                    let timeout = Duration::from_millis(ms as u64);
+                    self.ready.wait_timeout(state, timeout).unwrap().0
+                }
+                _ => self.ready.wait(state).unwrap(),
+            };
+            Self::deliver(&mut state, events);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poll::Poll;
+    use std::thread;
+
+    fn tokens(events: &Events) -> Vec<(usize, u32)> {
+        events
+            .iter()
+            .map(|event| (event.token(), event.events()))
+            .collect()
+    }
+
+    #[test]
+    fn delivers_injected_readiness_once() {
+        let selector = MockSelector::new();
+        selector.register(3, 7, ffi::EPOLL_IN).unwrap();
+        selector.set_ready(3, ffi::EPOLL_IN);
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(tokens(&events), [(7, ffi::EPOLL_IN as u32)]);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn filters_by_interests_but_always_reports_errors() {
+        let selector = MockSelector::new();
+        selector.register(3, 1, ffi::EPOLL_IN).unwrap();
+        selector.set_ready(3, ffi::EPOLLOUT);
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+        selector.set_ready(3, ffi::EPOLLOUT | ffi::EPOLLHUP);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(tokens(&events), [(1, ffi::EPOLLHUP as u32)]);
+    }
+
+    #[test]
+    fn oneshot_is_disarmed_until_reregistered() {
+        let selector = MockSelector::new();
+        selector
+            .register(3, 1, ffi::EPOLL_IN | ffi::EPOLLONESHOT)
+            .unwrap();
+        let mut events = Events::with_capacity(8);
+        selector.set_ready(3, ffi::EPOLL_IN);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(!selector.is_registered(3));
+        selector.set_ready(3, ffi::EPOLL_IN);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+        selector
+            .reregister(3, 2, ffi::EPOLL_IN | ffi::EPOLLONESHOT)
+            .unwrap();
+        selector.set_ready(3, ffi::EPOLL_IN);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(tokens(&events), [(2, ffi::EPOLL_IN as u32)]);
+    }
+
+    #[test]
+    fn keeps_events_beyond_the_capacity_for_the_next_call() {
+        let selector = MockSelector::new();
+        for fd in 0..3 {
+            selector.register(fd, fd as usize, ffi::EPOLL_IN).unwrap();
+            selector.set_ready(fd, ffi::EPOLL_IN);
+        }
+        let mut events = Events::with_capacity(2);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(events.len(), 2);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(tokens(&events), [(2, ffi::EPOLL_IN as u32)]);
+    }
+
+    #[test]
+    fn deregister_drops_pending_readiness() {
+        let selector = MockSelector::new();
+        selector.register(3, 1, ffi::EPOLL_IN).unwrap();
+        selector.set_ready(3, ffi::EPOLL_IN);
+        selector.deregister(3).unwrap();
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+        assert_eq!(
+            selector.deregister(3).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        selector.register(3, 1, ffi::EPOLL_IN).unwrap();
+        assert_eq!(
+            selector.register(3, 1, ffi::EPOLL_IN).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+    }
+
+    #[test]
+    fn blocking_poll_is_woken_from_another_thread() {
+        let mut poll = Poll::with_selector(MockSelector::new());
+        let registry = poll.registry().clone();
+        registry.selector().register(5, 42, ffi::EPOLL_IN).unwrap();
+        let injector = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            registry.selector().set_ready(5, ffi::EPOLL_IN);
+        });
+        let mut events = Events::with_capacity(8);
+        poll.poll(&mut events, None).unwrap();
+        assert_eq!(tokens(&events), [(42, ffi::EPOLL_IN as u32)]);
+        injector.join().unwrap();
+    }
+}
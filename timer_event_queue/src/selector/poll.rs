@@ -0,0 +1,320 @@
+use crate::{ffi, poll::Events, poll::Selector};
+use std::{
+    collections::HashMap,
+    io::{self, Result},
+    os::fd::RawFd,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+struct Registration {
+    token: usize,
+    interests: i32,
+    armed: bool,
+}
+
+/// Fallback selector built on `poll(2)`, which is available on every unix.
+///
+/// `poll(2)` only knows level-triggered readiness, so `EPOLLET` is rejected
+/// with `InvalidInput`: a source that stays ready (like a writable socket)
+/// can't be told apart from one that became ready again without reporting
+/// it on every call. `EPOLLONESHOT` registrations are disarmed once their
+/// event was delivered and have to be re-armed with `reregister`.
+pub struct PollSelector {
+    registrations: Mutex<HashMap<RawFd, Registration>>,
+    // Self-pipe used to interrupt a blocking `poll` when the registrations change
+    wake_read: RawFd,
+    wake_write: RawFd,
+}
+impl PollSelector {
+    pub fn new() -> Result<Self> {
+        let mut fds = [0i32; 2];
+        let res = unsafe { ffi::pipe2(fds.as_mut_ptr(), ffi::O_NONBLOCK | ffi::O_CLOEXEC) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            registrations: Mutex::new(HashMap::new()),
+            wake_read: fds[0],
+            wake_write: fds[1],
+        })
+    }
+
+    /// Interrupt a concurrently blocking `select`, so it picks up the new registrations
+    fn notify(&self) -> Result<()> {
+        let res = unsafe { ffi::write(self.wake_write, [1u8].as_ptr(), 1) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            // A full pipe already guarantees a wake up
+            if err.kind() != io::ErrorKind::WouldBlock {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn drain_wake_pipe(&self) {
+        let mut buf = [0u8; 64];
+        while unsafe { ffi::read(self.wake_read, buf.as_mut_ptr(), buf.len()) } > 0 {}
+    }
+}
+
+fn check_interests(interests: i32) -> Result<()> {
+    if interests & ffi::EPOLLET != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the poll(2) selector doesn't support edge-triggered (EPOLLET) registrations",
+        ));
+    }
+    Ok(())
+}
+
+fn to_poll_events(interests: i32) -> i16 {
+    let mut events = 0;
+    if interests & ffi::EPOLL_IN != 0 {
+        events |= ffi::POLLIN;
+    }
+    if interests & ffi::EPOLLOUT != 0 {
+        events |= ffi::POLLOUT;
+    }
+    if interests & ffi::EPOLLRDHUP != 0 {
+        events |= ffi::POLLRDHUP;
+    }
+    events
+}
+
+fn to_epoll_events(revents: i16) -> u32 {
+    let mut events = 0;
+    if revents & ffi::POLLIN != 0 {
+        events |= ffi::EPOLL_IN;
+    }
+    if revents & ffi::POLLOUT != 0 {
+        events |= ffi::EPOLLOUT;
+    }
+    if revents & (ffi::POLLERR | ffi::POLLNVAL) != 0 {
+        events |= ffi::EPOLLERR;
+    }
+    if revents & ffi::POLLHUP != 0 {
+        events |= ffi::EPOLLHUP;
+    }
+    if revents & ffi::POLLRDHUP != 0 {
+        events |= ffi::EPOLLRDHUP;
+    }
+    events as u32
+}
+
+impl Selector for PollSelector {
+    fn register(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        check_interests(interests)?;
+        let mut registrations = self.registrations.lock().unwrap();
+        if registrations.contains_key(&fd) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        registrations.insert(
+            fd,
+            Registration {
+                token,
+                interests,
+                armed: true,
+            },
+        );
+        drop(registrations);
+        self.notify()
+    }
+    fn reregister(&self, fd: RawFd, token: usize, interests: i32) -> Result<()> {
+        check_interests(interests)?;
+        let mut registrations = self.registrations.lock().unwrap();
+        let registration = registrations
+            .get_mut(&fd)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        registration.token = token;
+        registration.interests = interests;
+        registration.armed = true;
+        drop(registrations);
+        self.notify()
+    }
+    fn deregister(&self, fd: RawFd) -> Result<()> {
+        self.registrations
+            .lock()
+            .unwrap()
+            .remove(&fd)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+        self.notify()
+    }
+    fn select(&self, events: &mut Events, timeout: Option<i32>) -> Result<()> {
+        events.clear();
+        let deadline = timeout
+            .filter(|timeout| *timeout >= 0)
+            .map(|timeout| Instant::now() + Duration::from_millis(timeout as u64));
+        loop {
+            // Rebuilt on every pass, a wake up through the pipe means the registrations changed
+            let mut fds = vec![ffi::PollFd {
+                fd: self.wake_read,
+                events: ffi::POLLIN,
+                revents: 0,
+            }];
+            fds.extend(
+                self.registrations
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(_, registration)| registration.armed)
+                    .map(|(fd, registration)| ffi::PollFd {
+                        fd: *fd,
+                        events: to_poll_events(registration.interests),
+                        revents: 0,
+                    }),
+            );
+            let timeout = match deadline {
+                // Rounded up, so we don't spin on a deadline less than a millisecond away
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_micros()
+                    .div_ceil(1000)
+                    .min(i32::MAX as u128) as i32,
+                None => -1,
+            };
+            let res = unsafe { ffi::poll(fds.as_mut_ptr(), fds.len() as u64, timeout) };
+            if res < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if fds[0].revents != 0 {
+                self.drain_wake_pipe();
+            }
+
+            let mut registrations = self.registrations.lock().unwrap();
+            for pollfd in fds[1..].iter().filter(|pollfd| pollfd.revents != 0) {
+                if events.len() == events.capacity() {
+                    // The remaining fds are still ready and will be reported by the next call
+                    break;
+                }
+                // The fd could have been deregistered while we were blocked
+                let Some(registration) = registrations.get_mut(&pollfd.fd) else {
+                    continue;
+                };
+                if !registration.armed {
+                    continue;
+                }
+                events.push(ffi::Event {
+                    events: to_epoll_events(pollfd.revents),
+                    epoll_data: registration.token,
+                });
+                if registration.interests & ffi::EPOLLONESHOT != 0 {
+                    registration.armed = false;
+                }
+            }
+            if !events.is_empty() || timeout == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+impl Drop for PollSelector {
+    fn drop(&mut self) {
+        for fd in [self.wake_read, self.wake_write] {
+            let res = unsafe { ffi::close(fd) };
+            if res < 0 {
+                let err = io::Error::last_os_error();
+                eprintln!("ERROR: {err:?}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::Write, os::fd::AsRawFd, os::unix::net::UnixStream, sync::Arc, thread, time::Duration,
+    };
+
+    #[test]
+    fn rejects_edge_triggered_registrations() {
+        let selector = PollSelector::new().unwrap();
+        let (a, _b) = UnixStream::pair().unwrap();
+        let err = selector
+            .register(a.as_raw_fd(), 0, ffi::EPOLL_IN | ffi::EPOLLET)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        selector.register(a.as_raw_fd(), 0, ffi::EPOLL_IN).unwrap();
+        let err = selector
+            .reregister(a.as_raw_fd(), 0, ffi::EPOLL_IN | ffi::EPOLLET)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn reports_readiness_level_triggered() {
+        let selector = PollSelector::new().unwrap();
+        let (a, mut b) = UnixStream::pair().unwrap();
+        selector.register(a.as_raw_fd(), 9, ffi::EPOLL_IN).unwrap();
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+        b.write_all(b"x").unwrap();
+        // Reported again and again while the data is not read
+        for _ in 0..2 {
+            selector.select(&mut events, Some(0)).unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0].token(), 9);
+            assert_ne!(events[0].events() & ffi::EPOLL_IN as u32, 0);
+        }
+    }
+
+    #[test]
+    fn oneshot_is_disarmed_until_reregistered() {
+        let selector = PollSelector::new().unwrap();
+        let (a, mut b) = UnixStream::pair().unwrap();
+        let interests = ffi::EPOLL_IN | ffi::EPOLLONESHOT;
+        selector.register(a.as_raw_fd(), 1, interests).unwrap();
+        b.write_all(b"x").unwrap();
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(events.len(), 1);
+        selector.select(&mut events, Some(0)).unwrap();
+        assert!(events.is_empty());
+        selector.reregister(a.as_raw_fd(), 2, interests).unwrap();
+        selector.select(&mut events, Some(0)).unwrap();
+        assert_eq!(events[0].token(), 2);
+    }
+
+    #[test]
+    fn registering_wakes_a_blocking_select() {
+        let selector = Arc::new(PollSelector::new().unwrap());
+        let (a, mut b) = UnixStream::pair().unwrap();
+        b.write_all(b"x").unwrap();
+        let registrar = {
+            let selector = selector.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                selector.register(a.as_raw_fd(), 3, ffi::EPOLL_IN).unwrap();
+                a
+            })
+        };
+        let mut events = Events::with_capacity(8);
+        // The wake up only picks up the new registration, the event comes right after
+        selector.select(&mut events, None).unwrap();
+        assert_eq!(events[0].token(), 3);
+        registrar.join().unwrap();
+    }
+
+    #[test]
+    fn a_wake_up_without_events_keeps_waiting_until_the_timeout() {
+        let selector = Arc::new(PollSelector::new().unwrap());
+        let (a, _b) = UnixStream::pair().unwrap();
+        let registrar = {
+            let selector = selector.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                selector.register(a.as_raw_fd(), 4, ffi::EPOLL_IN).unwrap();
+                a
+            })
+        };
+        let start = Instant::now();
+        let mut events = Events::with_capacity(8);
+        selector.select(&mut events, Some(100)).unwrap();
+        assert!(events.is_empty());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        registrar.join().unwrap();
+    }
+}